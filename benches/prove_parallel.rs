@@ -0,0 +1,38 @@
+// Wall time of prove_many_parallel against proving the same instances one after the other,
+// `cargo bench --bench prove_parallel`. No harness, a single run of each is enough to see
+// the rayon pool at work
+use std::time::Instant;
+
+use halo2_circuits::{
+    dev::SelfVerifying,
+    prelude::*,
+    prover::prove_many_parallel,
+};
+use halo2_proofs::{pasta::EqAffine, poly::commitment::Params};
+
+const INSTANCES: u64 = 8;
+const STEPS: usize = 200;
+
+fn main() {
+    let circuits: Vec<_> = (1..=INSTANCES)
+        .map(|seed| FibonacciCircuit::new(Some(Fp::from(seed)), Some(Fp::from(seed + 1)), STEPS))
+        .collect();
+    let publics: Vec<_> = circuits.iter().map(|c| c.expected_public().remove(0)).collect();
+
+    // both include keygen, prove_many_parallel does it once too
+    let start = Instant::now();
+    let params = Params::<EqAffine>::new(circuits[0].min_k());
+    let pk = keygen(&params, &circuits[0]).unwrap();
+    for (circuit, public) in circuits.iter().zip(&publics) {
+        prove(&params, &pk, circuit.clone(), public, ProofRng::os().unwrap()).unwrap();
+    }
+    let sequential = start.elapsed();
+
+    let start = Instant::now();
+    prove_many_parallel(circuits, publics).unwrap();
+    let parallel = start.elapsed();
+
+    println!("{} proofs of {} steps", INSTANCES, STEPS);
+    println!("sequential: {:?}", sequential);
+    println!("parallel:   {:?} ({} threads)", parallel, rayon::current_num_threads());
+}
@@ -0,0 +1,185 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    dev::find_min_k,
+    fibonacci::{cell_value, ACell},
+    pool::ColumnPool,
+};
+
+// a + b = c, a - b = c and a * b = c on the same three columns, one selector per operation.
+// The selectors are always on different rows, which is what lets keygen fold simple
+// selectors into fewer fixed columns. With combine = false they are complex selectors,
+// which keygen never combines: one fixed column each, easier to read in a debugger
+#[derive(Debug, Clone)]
+pub struct ArithConfig {
+    pub advice: [Column<Advice>; 3],
+    pub add: Selector,
+    pub sub: Selector,
+    pub mul: Selector,
+}
+
+type BinOp<F> = fn(Expression<F>, Expression<F>) -> Expression<F>;
+
+pub struct ArithChip<F: FieldExt> {
+    config: ArithConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ArithChip<F> {
+    pub fn construct(config: ArithConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, combine: bool) -> ArithConfig {
+        let pool = ColumnPool::new(meta, 3);
+        Self::configure_with_columns(meta, &pool, combine)
+    }
+
+    pub fn configure_with_columns(meta: &mut ConstraintSystem<F>, pool: &ColumnPool, combine: bool) -> ArithConfig {
+        let advice: [Column<Advice>; 3] = pool.advice();
+        let mut selector = || if combine { meta.selector() } else { meta.complex_selector() };
+        let (add, sub, mul) = (selector(), selector(), selector());
+
+        let ops: [(&str, Selector, BinOp<F>); 3] = [
+            ("arith add", add, |a, b| a + b),
+            ("arith sub", sub, |a, b| a - b),
+            ("arith mul", mul, |a, b| a * b),
+        ];
+        for (name, select, op) in ops {
+            meta.create_gate(name, |meta| {
+                // col_a | col_b | col_c | add | sub | mul
+                //   a      b       c       one of them
+                let s = meta.query_selector(select);
+                let [a, b, c] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+                vec![s * (op(a, b) - c)]
+            });
+        }
+
+        ArithConfig { advice, add, sub, mul }
+    }
+
+    pub fn add(&self, layouter: impl Layouter<F>, a: &ACell<F>, b: &ACell<F>) -> Result<ACell<F>, Error> {
+        self.op(layouter, self.config.add, a, b, |a, b| a + b)
+    }
+
+    pub fn sub(&self, layouter: impl Layouter<F>, a: &ACell<F>, b: &ACell<F>) -> Result<ACell<F>, Error> {
+        self.op(layouter, self.config.sub, a, b, |a, b| a - b)
+    }
+
+    pub fn mul(&self, layouter: impl Layouter<F>, a: &ACell<F>, b: &ACell<F>) -> Result<ACell<F>, Error> {
+        self.op(layouter, self.config.mul, a, b, |a, b| a * b)
+    }
+
+    fn op(&self, mut layouter: impl Layouter<F>, select: Selector, a: &ACell<F>, b: &ACell<F>, f: impl Fn(F, F) -> F) -> Result<ACell<F>, Error> {
+        layouter.assign_region(|| "arith", |mut region| {
+            select.enable(&mut region, 0)?;
+
+            a.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+            b.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+
+            let c_val = cell_value(a).and_then(|a| cell_value(b).map(|b| f(a, b)));
+            region.assign_advice(|| "c", self.config.advice[2], 0,
+                || c_val.ok_or(Error::Synthesis)).map(ACell)
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ArithCircuitConfig {
+    pub arith: ArithConfig,
+    pub instance: Column<Instance>,
+}
+
+// Private x and y, public x + y, x - y and x * y. COMBINE picks the selector kind, see ArithChip
+#[derive(Default, Clone)]
+pub struct ArithCircuit<F, const COMBINE: bool> {
+    pub x: Option<F>,
+    pub y: Option<F>,
+}
+
+impl<F: FieldExt, const COMBINE: bool> Circuit<F> for ArithCircuit<F, COMBINE> {
+    type Config = ArithCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { x: None, y: None }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        ArithCircuitConfig { arith: ArithChip::configure(meta, COMBINE), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let [col_x, col_y, _] = config.arith.advice;
+        let (x, y) = layouter.assign_region(|| "inputs", |mut region| {
+            let x = region.assign_advice(|| "x", col_x, 0, || self.x.ok_or(Error::Synthesis)).map(ACell)?;
+            let y = region.assign_advice(|| "y", col_y, 0, || self.y.ok_or(Error::Synthesis)).map(ACell)?;
+            Ok((x, y))
+        })?;
+
+        let chip = ArithChip::construct(config.arith);
+        let results = [
+            chip.add(layouter.namespace(|| "x + y"), &x, &y)?,
+            chip.sub(layouter.namespace(|| "x - y"), &x, &y)?,
+            chip.mul(layouter.namespace(|| "x * y"), &x, &y)?,
+        ];
+        for (row, result) in results.iter().enumerate() {
+            layouter.constrain_instance(result.0.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+// Mock proves the three results for x and y, with combined selectors or not
+pub fn prove_arith<const COMBINE: bool>(x: Fp, y: Fp) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = ArithCircuit::<Fp, COMBINE> { x: Some(x), y: Some(y) };
+    let public = vec![vec![x + y, x - y, x * y]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::EqAffine, poly::commitment::Params};
+
+    use super::*;
+    use crate::inspect::fixed_columns;
+
+    fn fixed_of<const COMBINE: bool>() -> usize {
+        let params = Params::<EqAffine>::new(4);
+        fixed_columns(&keygen_vk(&params, &ArithCircuit::<Fp, COMBINE>::default()).expect("keygen failed"))
+    }
+
+    #[test]
+    fn combined_selectors_take_fewer_fixed_columns() {
+        // the degree bound keeps keygen from folding all three into one column
+        assert_eq!(fixed_of::<true>(), 2);
+        assert_eq!(fixed_of::<false>(), 3);
+    }
+
+    #[test]
+    fn every_operation_proves_either_way() {
+        let (x, y) = (Fp::from(7), Fp::from(3));
+        assert_eq!(prove_arith::<true>(x, y), Ok(()));
+        assert_eq!(prove_arith::<false>(x, y), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_wrong_result() {
+        let circuit = ArithCircuit::<Fp, true> { x: Some(Fp::from(7)), y: Some(Fp::from(3)) };
+        // 7 * 3 claimed as 20
+        crate::assert_circuit_fails!(circuit, 4, vec![vec![Fp::from(10), Fp::from(4), Fp::from(20)]],
+            VerifyFailure::Permutation { .. });
+    }
+}
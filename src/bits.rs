@@ -0,0 +1,108 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    cursor::RowCursor,
+    fibonacci::{cell_value, ACell},
+    pool::ColumnPool,
+};
+
+// Decomposes a cell into `width` bits, one row per bit, most significant bit first.
+// The running sum doubles each row and adds the bit, and has to end equal to the input,
+// so this doubles as a range check to [0, 2^width)
+#[derive(Debug, Clone)]
+pub struct BitsConfig {
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub first: Selector,
+    pub next: Selector,
+}
+
+pub struct BitsChip<F: FieldExt> {
+    config: BitsConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> BitsChip<F> {
+    pub fn construct(config: BitsConfig) -> Self {
+        Self { config, _marker: std::marker::PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> BitsConfig {
+        let pool = ColumnPool::new(meta, 2);
+        Self::configure_with_columns(meta, &pool)
+    }
+
+    // On the first two columns of the pool
+    pub fn configure_with_columns(meta: &mut ConstraintSystem<F>, pool: &ColumnPool) -> BitsConfig {
+        let [bit, acc] = pool.advice();
+        let first = meta.selector();
+        let next = meta.selector();
+
+        meta.create_gate("first bit", |meta| {
+            // bit | acc        | selector
+            //  b0 | b0         | first
+            //  b1 | 2*acc + b1 | next
+            let first = meta.query_selector(first);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                first.clone() * bit.clone() * (one - bit.clone()),
+                first * (acc - bit),
+            ]
+        });
+
+        meta.create_gate("next bit", |meta| {
+            let next = meta.query_selector(next);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let one = Expression::Constant(F::one());
+            let two = Expression::Constant(F::from(2));
+            vec![
+                next.clone() * bit.clone() * (one - bit.clone()),
+                next * (acc_cur - (two * acc_prev + bit)),
+            ]
+        });
+
+        BitsConfig { bit, acc, first, next }
+    }
+
+    // Returns the bit cells least significant bit first
+    pub fn decompose(&self, mut layouter: impl Layouter<F>, value: &ACell<F>, width: usize) -> Result<Vec<ACell<F>>, Error> {
+        assert!((1..=128).contains(&width), "width has to be in 1..=128");
+        let value_bits = cell_value(value).map(|v| v.get_lower_128());
+
+        layouter.assign_region(|| "bits", |mut region| {
+            let mut cursor = RowCursor::new(&mut region);
+            let mut bits = vec![];
+            let mut acc_val = Some(F::zero());
+            let mut acc_cell = None;
+            for row in 0..width {
+                if row > 0 {
+                    cursor.next_row();
+                    cursor.enable(self.config.next)?;
+                } else {
+                    cursor.enable(self.config.first)?;
+                }
+
+                let bit_val = value_bits.map(|v| F::from(((v >> (width - 1 - row)) & 1) as u64));
+                let bit = cursor.assign("bit", self.config.bit, bit_val)?;
+
+                acc_val = acc_val.and_then(|acc| bit_val.map(|b| acc.double() + b));
+                acc_cell = Some(cursor.assign("acc", self.config.acc, acc_val)?);
+                bits.push(bit);
+            }
+
+            // the running sum has to land on the value
+            cursor.region().constrain_equal(acc_cell.unwrap().0.cell(), value.0.cell())?;
+
+            bits.reverse();
+            Ok(bits)
+        })
+    }
+}
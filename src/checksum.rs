@@ -0,0 +1,146 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::fibonacci::{cell_value, ACell};
+
+// Polynomial checksum sum(value_i * base^i) over a list of cells, for tamper evidence:
+// the verifier compares the exposed checksum to one it computed itself.
+// Evaluated with Horner's rule from the last cell down, so the running sum is one row each
+#[derive(Debug, Clone)]
+pub struct ChecksumConfig {
+    pub value: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub base: Column<Fixed>,
+    pub first: Selector,
+    pub next: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct ChecksumChip<F: FieldExt> {
+    config: ChecksumConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ChecksumChip<F> {
+    pub fn construct(config: ChecksumConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> ChecksumConfig {
+        let value = meta.advice_column();
+        let acc = meta.advice_column();
+        let base = meta.fixed_column();
+        let first = meta.selector();
+        let next = meta.selector();
+
+        meta.enable_equality(value);
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("checksum first", |meta| {
+            // value   | acc                  | base | selector
+            // v_{n-1} | v_{n-1}              |      | first
+            // v_{n-2} | acc * base + v_{n-2} |  x   | next
+            let first = meta.query_selector(first);
+            let v = meta.query_advice(value, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            vec![first * (acc - v)]
+        });
+
+        meta.create_gate("checksum next", |meta| {
+            let next = meta.query_selector(next);
+            let v = meta.query_advice(value, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let base = meta.query_fixed(base, Rotation::cur());
+            vec![next * (acc_cur - (acc_prev * base + v))]
+        });
+
+        ChecksumConfig { value, acc, base, first, next, instance }
+    }
+
+    pub fn checksum(&self, mut layouter: impl Layouter<F>, cells: &[ACell<F>], base: F) -> Result<ACell<F>, Error> {
+        assert!(!cells.is_empty(), "nothing to checksum");
+
+        layouter.assign_region(|| "checksum", |mut region| {
+            let mut acc_val = Some(F::zero());
+            let mut acc_cell = None;
+            for (row, cell) in cells.iter().rev().enumerate() {
+                if row == 0 {
+                    self.config.first.enable(&mut region, row)?;
+                } else {
+                    self.config.next.enable(&mut region, row)?;
+                }
+                region.assign_fixed(|| "base", self.config.base, row, || Ok(base))?;
+                cell.0.copy_advice(|| "value", &mut region, self.config.value, row)?;
+
+                acc_val = acc_val.and_then(|acc| cell_value(cell).map(|v| acc * base + v));
+                acc_cell = Some(region.assign_advice(|| "acc", self.config.acc, row,
+                    || acc_val.ok_or(Error::Synthesis)).map(ACell)?);
+            }
+            Ok(acc_cell.unwrap())
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &ACell<F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+// The same checksum outside the circuit
+pub fn checksum_native<F: FieldExt>(values: &[F], base: F) -> F {
+    values.iter().rev().fold(F::zero(), |acc, v| acc * base + v)
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::fibonacci::{FiboChip, FiboConfig, FibonacciCircuit};
+
+    const BASE: u64 = 7;
+
+    // Checksum of f(1)..f(9) from seeds 1, 1 at instance row 0
+    #[derive(Default)]
+    struct TraceChecksum;
+
+    impl Circuit<Fp> for TraceChecksum {
+        type Config = (FiboConfig, ChecksumConfig);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            (FiboChip::configure(meta, instance), ChecksumChip::configure(meta, instance))
+        }
+
+        fn synthesize(&self, (fibo, config): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let trace = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9).with_private_seeds();
+            let cells = trace.compute_all(&FiboChip::construct(fibo), layouter.namespace(|| "trace"))?;
+            let chip = ChecksumChip::construct(config);
+            let sum = chip.checksum(layouter.namespace(|| "checksum"), &cells[1..], Fp::from(BASE))?;
+            chip.expose_public(layouter.namespace(|| "checksum"), &sum, 0)
+        }
+    }
+
+    #[test]
+    fn matches_the_native_checksum() {
+        // f(1)..f(9) and sum(f(i) * base^(i-1)) in plain integers
+        let terms = [1u64, 2, 3, 5, 8, 13, 21, 34, 55];
+        let expected: u64 = terms.iter().enumerate().map(|(i, t)| t * BASE.pow(i as u32)).sum();
+        assert_eq!(checksum_native(&terms.map(Fp::from), Fp::from(BASE)), Fp::from(expected));
+
+        crate::assert_circuit!(TraceChecksum, 5, vec![vec![Fp::from(expected)]]);
+        crate::assert_circuit_fails!(TraceChecksum, 5, vec![vec![Fp::from(expected + 1)]], VerifyFailure::Permutation { .. });
+    }
+}
@@ -0,0 +1,38 @@
+use std::{env, fs, process};
+
+use halo2_circuits::{
+    dev::assert_fibonacci_vk_snapshot,
+    fibonacci::FibonacciCircuit,
+    inspect::{analyze, inspect_proof},
+    prelude::Fp,
+};
+
+fn usage() -> ! {
+    eprintln!("usage: halo2-circuits inspect <proof file>");
+    eprintln!("       halo2-circuits analyze <steps>");
+    eprintln!("       halo2-circuits vk-snapshot");
+    process::exit(2);
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["inspect", path] => {
+            let bytes = fs::read(path).unwrap_or_else(|e| {
+                eprintln!("could not read {}: {}", path, e);
+                process::exit(1);
+            });
+            println!("{}", inspect_proof(&bytes));
+        }
+        ["analyze", steps] => {
+            let steps = steps.parse().unwrap_or_else(|_| usage());
+            let circuit = FibonacciCircuit::<Fp>::new(None, None, steps);
+            println!("{}", analyze(&circuit));
+        }
+        ["vk-snapshot"] => {
+            assert_fibonacci_vk_snapshot();
+            println!("verifying key matches the snapshot");
+        }
+        _ => usage(),
+    }
+}
@@ -0,0 +1,165 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    divmod::{DivModChip, DivModConfig},
+    dev::find_min_k,
+    fibonacci::{cell_value, ACell},
+    layouter_ext::LayouterExt,
+    linear::LinearChip,
+    mul::MulChip,
+};
+
+// One Collatz step, n / 2 for even n and 3n + 1 for odd n. divmod by 2 gives q = n / 2 and
+// the parity bit r (range checked, and r < 2). With e = 3n + 1 - q the step is q + r * e,
+// the bit times e being the select. e and the result are witnessed and pinned by linear
+// combinations, the product goes through the mul gate. n has to fit in DIVMOD_BITS, so a
+// chain of steps stops being provable once a 3n + 1 leaves 64 bits
+#[derive(Debug, Clone)]
+pub struct CollatzConfig {
+    pub divmod: DivModConfig,
+}
+
+pub struct CollatzChip<F: FieldExt> {
+    config: CollatzConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> CollatzChip<F> {
+    pub fn construct(config: CollatzConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> CollatzConfig {
+        CollatzConfig { divmod: DivModChip::configure(meta) }
+    }
+
+    pub fn collatz_step(&self, mut layouter: impl Layouter<F>, n: &ACell<F>) -> Result<ACell<F>, Error> {
+        let [col_two, col_e, col_out] = self.config.divmod.mul.advice;
+        // the mul chip's constant column takes the divisor
+        let two = layouter.assign_region(|| "two", |mut region| {
+            region.assign_advice_from_constant(|| "two", col_two, 0, F::from(2)).map(ACell)
+        })?;
+        let (q, r) = DivModChip::construct(self.config.divmod.clone()).divmod(layouter.namespace(|| "n / 2"), n, &two)?;
+
+        let three = F::from(3);
+        let e_val = cell_value(n).and_then(|n| cell_value(&q).map(|q| three * n + F::one() - q));
+        let e = layouter.assign_value("3n + 1 - q", col_e, e_val)?;
+        let out_val = cell_value(&q).and_then(|q| cell_value(&r).and_then(|r| e_val.map(|e| q + r * e)));
+        let out = layouter.assign_value("step", col_out, out_val)?;
+
+        let re = MulChip::construct(self.config.divmod.mul.clone()).mul(layouter.namespace(|| "r * e"), &r, &e)?;
+
+        let linear = LinearChip::construct(self.config.divmod.linear.clone());
+        linear.linear_combination_eq(layouter.namespace(|| "e = 3n + 1 - q"), &[n.clone(), q.clone(), e],
+            &[three, -F::one(), -F::one()], -F::one())?;
+        linear.linear_combination_eq(layouter.namespace(|| "step = q + r * e"), &[out.clone(), q, re],
+            &[F::one(), -F::one(), -F::one()], F::zero())?;
+
+        Ok(out)
+    }
+}
+
+// The same step outside the circuit. u64::is_multiple_of needs Rust 1.87, which the crate
+// doesn't ask for
+#[allow(clippy::manual_is_multiple_of)]
+pub fn collatz_native(n: u64) -> u64 {
+    if n % 2 == 0 { n / 2 } else { 3 * n + 1 }
+}
+
+#[derive(Debug, Clone)]
+pub struct CollatzCircuitConfig {
+    pub collatz: CollatzConfig,
+    pub instance: Column<Instance>,
+}
+
+// Private start, the values after each of the steps public, in order
+#[derive(Default, Clone)]
+pub struct CollatzCircuit<F> {
+    pub start: Option<u64>,
+    pub steps: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> CollatzCircuit<F> {
+    pub fn new(start: u64, steps: usize) -> Self {
+        Self { start: Some(start), steps, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for CollatzCircuit<F> {
+    type Config = CollatzCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { start: None, ..self.clone() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        CollatzCircuitConfig { collatz: CollatzChip::configure(meta), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let mut n = layouter.assign_value("start", config.collatz.divmod.mul.advice[0], self.start.map(F::from))?;
+        let chip = CollatzChip::construct(config.collatz);
+        for row in 0..self.steps {
+            n = chip.collatz_step(layouter.namespace(|| "collatz step"), &n)?;
+            layouter.constrain_instance(n.0.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+// Mock proves `steps` Collatz steps from start, against the native values of each step
+pub fn prove_collatz(start: u64, steps: usize) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = CollatzCircuit::<Fp>::new(start, steps);
+    let public = vec![
+        std::iter::successors(Some(start), |&n| Some(collatz_native(n)))
+            .skip(1)
+            .take(steps)
+            .map(Fp::from)
+            .collect(),
+    ];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(values: &[u64]) -> Vec<Vec<Fp>> {
+        vec![values.iter().map(|&n| Fp::from(n)).collect()]
+    }
+
+    #[test]
+    fn halves_6_and_triples_5() {
+        assert_eq!((collatz_native(6), collatz_native(5)), (3, 16));
+        crate::assert_circuit!(CollatzCircuit::<Fp>::new(6, 1), 10, public(&[3]));
+        crate::assert_circuit!(CollatzCircuit::<Fp>::new(5, 1), 10, public(&[16]));
+    }
+
+    #[test]
+    fn reaches_1_from_6() {
+        let chain = [3, 10, 5, 16, 8, 4, 2, 1];
+        crate::assert_circuit!(CollatzCircuit::<Fp>::new(6, chain.len()), 12, public(&chain));
+        assert_eq!(prove_collatz(6, chain.len()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_wrong_intermediate_value() {
+        // 10 / 2 claimed as 6
+        crate::assert_circuit_fails!(CollatzCircuit::<Fp>::new(6, 3), 12, public(&[3, 10, 6]),
+            VerifyFailure::Permutation { .. });
+    }
+}
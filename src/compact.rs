@@ -0,0 +1,121 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    dev::SelfVerifying,
+    fibonacci::{fib_native, ACell},
+};
+
+// The same statement as FibonacciCircuit, f(steps) = out for public seeds, on a single
+// advice column with one term per row. The gate looks two rows ahead instead of copying
+// values between columns, so there are no copy constraints inside the trace at all.
+// Kept small and independent of FiboChip so the two can be checked against each other
+#[derive(Debug, Clone)]
+pub struct CompactFiboConfig {
+    pub advice: Column<Advice>,
+    pub select: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct CompactFiboChip<F: FieldExt> {
+    config: CompactFiboConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> CompactFiboChip<F> {
+    pub fn construct(config: CompactFiboConfig) -> Self {
+        Self { config, _marker: std::marker::PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> CompactFiboConfig {
+        let advice = meta.advice_column();
+        let select = meta.selector();
+
+        meta.enable_equality(advice);
+        meta.enable_equality(instance);
+
+        meta.create_gate("compact add", |meta| {
+            // advice | selector
+            //   a        s
+            //   b
+            //   c
+            let s = meta.query_selector(select);
+            let a = meta.query_advice(advice, Rotation::cur());
+            let b = meta.query_advice(advice, Rotation::next());
+            let c = meta.query_advice(advice, Rotation(2));
+            vec![s * (a + b - c)]
+        });
+
+        CompactFiboConfig { advice, select, instance }
+    }
+
+    // f(0)..=f(steps), one row each. Always at least f(0) and f(1), so both seeds have a cell
+    pub fn assign(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>, steps: usize) -> Result<Vec<ACell<F>>, Error> {
+        layouter.assign_region(|| "compact trace", |mut region| {
+            let (mut prev, mut cur) = (a, b);
+            let mut cells = vec![];
+            for row in 0..=steps.max(1) {
+                if row + 2 <= steps {
+                    self.config.select.enable(&mut region, row)?;
+                }
+                cells.push(region.assign_advice(|| "f", self.config.advice, row,
+                    || prev.ok_or(Error::Synthesis)).map(ACell)?);
+                let next = prev.and_then(|p| cur.map(|c| p + c));
+                prev = cur;
+                cur = next;
+            }
+            Ok(cells)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &ACell<F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct CompactFibonacciCircuit<F> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    pub steps: usize,
+}
+
+impl<F: FieldExt> CompactFibonacciCircuit<F> {
+    pub fn new(a: Option<F>, b: Option<F>, steps: usize) -> Self {
+        Self { a, b, steps }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for CompactFibonacciCircuit<F> {
+    type Config = CompactFiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { a: None, b: None, ..self.clone() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        CompactFiboChip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = CompactFiboChip::construct(config);
+        let cells = chip.assign(layouter.namespace(|| "trace"), self.a, self.b, self.steps)?;
+
+        chip.expose_public(layouter.namespace(|| "a"), &cells[0], 0)?;
+        chip.expose_public(layouter.namespace(|| "b"), &cells[1], 1)?;
+        chip.expose_public(layouter.namespace(|| "output"), &cells[self.steps], 2)
+    }
+}
+
+impl<F: FieldExt> SelfVerifying<F> for CompactFibonacciCircuit<F> {
+    fn expected_public(&self) -> Vec<Vec<F>> {
+        let (a, b) = (self.a.expect("seed a"), self.b.expect("seed b"));
+        vec![vec![a, b, fib_native(a, b, self.steps)]]
+    }
+}
@@ -0,0 +1,105 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    dev::find_min_k,
+    fibonacci::{fib_native, ACell, FiboChip, FiboConfig, FibonacciCircuit},
+};
+
+// The depth d convergent p/q of the golden ratio's continued fraction [1; 1, 1, ..].
+// p and q follow the Fibonacci recurrence from p_0/q_0 = 1/1, so with f(0) = f(1) = 1
+// that's p = f(d + 1) and q = f(d): 1/1, 2/1, 3/2, 5/3, ...
+// The trace is FiboChip's unit_trace, seeds pinned to 1
+pub struct ConvergentChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ConvergentChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        FibonacciCircuit::configure(meta)
+    }
+
+    // Returns (p, q)
+    pub fn convergent(&self, layouter: impl Layouter<F>, depth: usize) -> Result<(ACell<F>, ACell<F>), Error> {
+        let cells = FiboChip::construct(self.config.clone())
+            .unit_trace(layouter, depth + 1)?;
+        Ok((cells[depth + 1].clone(), cells[depth].clone()))
+    }
+}
+
+// (p, q) outside the circuit
+pub fn convergent_native(depth: usize) -> (u64, u64) {
+    let f = |n| fib_native(Fp::one(), Fp::one(), n).get_lower_128() as u64;
+    (f(depth + 1), f(depth))
+}
+
+// Exposes p and q at instance rows 0 and 1
+#[derive(Default, Clone)]
+pub struct ConvergentCircuit<F> {
+    pub depth: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ConvergentCircuit<F> {
+    pub fn new(depth: usize) -> Self {
+        Self { depth, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for ConvergentCircuit<F> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        ConvergentChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fibo = FiboChip::construct(config.clone());
+        let (p, q) = ConvergentChip::construct(config).convergent(layouter.namespace(|| "convergent"), self.depth)?;
+        fibo.expose_public(layouter.namespace(|| "p"), &p, 0)?;
+        fibo.expose_public(layouter.namespace(|| "q"), &q, 1)
+    }
+}
+
+// Mock proves that p/q is the depth d convergent
+pub fn prove_convergent(depth: usize, p: u64, q: u64) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = ConvergentCircuit::<Fp>::new(depth);
+    let public = vec![vec![Fp::from(p), Fp::from(q)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depth_5_is_13_over_8() {
+        assert_eq!(convergent_native(5), (13, 8));
+        assert_eq!(prove_convergent(5, 13, 8), Ok(()));
+    }
+
+    #[test]
+    fn rejects_the_next_convergent() {
+        let failures = prove_convergent(5, 21, 13).unwrap_err();
+        assert!(failures.iter().any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+}
@@ -0,0 +1,226 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    error::FiboError,
+    fibonacci::{cell_value, ACell},
+};
+
+// x is read as an integer below 2^CTZ_BITS, and odd is range checked to the same width
+pub const CTZ_BITS: usize = 64;
+
+// Trailing zero count: x = odd * 2^k with odd's lowest bit set. The pair (k, 2^k) is looked
+// up in a table of the powers of two, and odd is decomposed into bits with the lowest one
+// pinned to 1. odd < 2^64 and 2^k < 2^64 so the product can't wrap around the modulus.
+// x = 0 has no such form, ctz rejects it up front (and no witness could satisfy the gate)
+#[derive(Debug, Clone)]
+pub struct CtzConfig {
+    pub advice: [Column<Advice>; 4], // x, k, odd, 2^k
+    pub table: [TableColumn; 2],     // k, 2^k
+    pub select: Selector,
+    pub bits: BitsConfig,
+}
+
+pub struct CtzChip<F: FieldExt> {
+    config: CtzConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> CtzChip<F> {
+    pub fn construct(config: CtzConfig) -> Self {
+        Self { config, _marker: std::marker::PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> CtzConfig {
+        let col_x = meta.advice_column();
+        let col_k = meta.advice_column();
+        let col_odd = meta.advice_column();
+        let col_pow = meta.advice_column();
+        let table = [meta.lookup_table_column(), meta.lookup_table_column()];
+        // lookups can't use simple selectors
+        let select = meta.complex_selector();
+        // for pinning the lowest bit of odd
+        let constant = meta.fixed_column();
+
+        meta.enable_constant(constant);
+        meta.enable_equality(col_x);
+        meta.enable_equality(col_k);
+        meta.enable_equality(col_odd);
+
+        meta.create_gate("ctz", |meta| {
+            // col_x | col_k | col_odd | col_pow | selector
+            //   x      k       odd       2^k        s
+            let s = meta.query_selector(select);
+            let x = meta.query_advice(col_x, Rotation::cur());
+            let odd = meta.query_advice(col_odd, Rotation::cur());
+            let pow = meta.query_advice(col_pow, Rotation::cur());
+            vec![s * (odd * pow - x)]
+        });
+
+        // (k, 2^k) is in the table. A disabled row looks up (0, 1), which is the k = 0 entry
+        meta.lookup(|meta| {
+            let s = meta.query_selector(select);
+            let k = meta.query_advice(col_k, Rotation::cur());
+            let pow = meta.query_advice(col_pow, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                (s.clone() * k, table[0]),
+                (s * (pow - one.clone()) + one, table[1]),
+            ]
+        });
+
+        CtzConfig {
+            advice: [col_x, col_k, col_odd, col_pow],
+            table,
+            select,
+            bits: BitsChip::configure(meta),
+        }
+    }
+
+    // (k, 2^k) for k < CTZ_BITS
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "powers of two", |mut table| {
+            for k in 0..CTZ_BITS {
+                table.assign_cell(|| "k", self.config.table[0], k, || Ok(F::from(k as u64)))?;
+                table.assign_cell(|| "2^k", self.config.table[1], k, || Ok(F::from_u128(1 << k)))?;
+            }
+            Ok(())
+        })
+    }
+
+    // Returns (k, odd)
+    pub fn ctz(&self, mut layouter: impl Layouter<F>, x: &ACell<F>) -> Result<(ACell<F>, ACell<F>), FiboError> {
+        let x_val = cell_value(x).map(|x| x.get_lower_128() as u64);
+        if x_val == Some(0) {
+            return Err(FiboError::ZeroValue);
+        }
+        let k_val = x_val.map(|x| x.trailing_zeros());
+
+        let (k, odd) = layouter.assign_region(|| "ctz", |mut region| {
+            self.config.select.enable(&mut region, 0)?;
+
+            x.0.copy_advice(|| "x", &mut region, self.config.advice[0], 0)?;
+            let k = region.assign_advice(|| "k", self.config.advice[1], 0,
+                || k_val.map(|k| F::from(k as u64)).ok_or(Error::Synthesis)).map(ACell)?;
+            let odd = region.assign_advice(|| "odd", self.config.advice[2], 0,
+                || x_val.map(|x| F::from(x >> x.trailing_zeros())).ok_or(Error::Synthesis)).map(ACell)?;
+            region.assign_advice(|| "2^k", self.config.advice[3], 0,
+                || k_val.map(|k| F::from(1 << k)).ok_or(Error::Synthesis))?;
+            Ok((k, odd))
+        })?;
+
+        let bits = BitsChip::construct(self.config.bits.clone())
+            .decompose(layouter.namespace(|| "odd bits"), &odd, CTZ_BITS)?;
+        // a cell of its own for the 1, MockProver can't locate failures past an empty region
+        layouter.assign_region(|| "odd", |mut region| {
+            let one = region.assign_advice_from_constant(|| "lowest bit", self.config.bits.bit, 0, F::one())?;
+            region.constrain_equal(one.cell(), bits[0].0.cell())
+        })?;
+
+        Ok((k, odd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::layouter_ext::LayouterExt;
+
+    // ctz of x from instance row 0, k and odd at rows 1 and 2. A forged k hand-writes the ctz
+    // row with odd = x >> k instead, and pins the lowest bit of odd the same way ctz does
+    struct CtzCircuit {
+        forged_k: Option<u32>,
+        error: RefCell<Option<FiboError>>,
+    }
+
+    impl CtzCircuit {
+        fn new(forged_k: Option<u32>) -> Self {
+            Self { forged_k, error: RefCell::new(None) }
+        }
+    }
+
+    impl Circuit<Fp> for CtzCircuit {
+        type Config = (CtzConfig, Column<Instance>);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::new(self.forged_k)
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (CtzChip::configure(meta), instance)
+        }
+
+        fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = CtzChip::construct(config.clone());
+            chip.load(&mut layouter)?;
+            let x = layouter.assign_from_instance("x", instance, 0, config.advice[0])?;
+
+            let (k, odd) = match self.forged_k {
+                None => match chip.ctz(layouter.namespace(|| "ctz"), &x) {
+                    Ok(cells) => cells,
+                    Err(e) => {
+                        *self.error.borrow_mut() = Some(e);
+                        return Err(Error::Synthesis);
+                    }
+                },
+                Some(k) => {
+                    let odd = cell_value(&x).map(|x| Fp::from(x.get_lower_128() as u64 >> k));
+                    let (k, odd) = layouter.assign_region(|| "forged ctz", |mut region| {
+                        config.select.enable(&mut region, 0)?;
+                        x.0.copy_advice(|| "x", &mut region, config.advice[0], 0)?;
+                        let k_cell = region.assign_advice(|| "k", config.advice[1], 0, || Ok(Fp::from(k as u64))).map(ACell)?;
+                        let odd = region.assign_advice(|| "odd", config.advice[2], 0, || odd.ok_or(Error::Synthesis)).map(ACell)?;
+                        region.assign_advice(|| "2^k", config.advice[3], 0, || Ok(Fp::from(1 << k)))?;
+                        Ok((k_cell, odd))
+                    })?;
+                    let bits = BitsChip::construct(config.bits.clone())
+                        .decompose(layouter.namespace(|| "odd bits"), &odd, CTZ_BITS)?;
+                    layouter.assign_region(|| "odd", |mut region| {
+                        let one = region.assign_advice_from_constant(|| "lowest bit", config.bits.bit, 0, Fp::one())?;
+                        region.constrain_equal(one.cell(), bits[0].0.cell())
+                    })?;
+                    (k, odd)
+                }
+            };
+            layouter.constrain_instance(k.0.cell(), instance, 1)?;
+            layouter.constrain_instance(odd.0.cell(), instance, 2)
+        }
+    }
+
+    fn public(x: u64, k: u64, odd: u64) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(x), Fp::from(k), Fp::from(odd)]]
+    }
+
+    #[test]
+    fn counts_trailing_zeros() {
+        crate::assert_circuit!(CtzCircuit::new(None), 8, public(12, 2, 3));
+        crate::assert_circuit!(CtzCircuit::new(None), 8, public(8, 3, 1));
+        crate::assert_circuit!(CtzCircuit::new(None), 8, public(7, 0, 7));
+        crate::assert_circuit_fails!(CtzCircuit::new(None), 8, public(12, 1, 6), VerifyFailure::Permutation { .. });
+    }
+
+    #[test]
+    fn rejects_an_even_odd_part() {
+        // 12 = 6 * 2^1 passes the gate and the table, but 6 has its lowest bit clear
+        crate::assert_circuit_fails!(CtzCircuit::new(Some(1)), 8, public(12, 1, 6), VerifyFailure::Permutation { .. });
+    }
+
+    #[test]
+    fn rejects_zero_up_front() {
+        let circuit = CtzCircuit::new(None);
+        assert!(halo2_proofs::dev::MockProver::run(8, &circuit, public(0, 0, 0)).is_err());
+        assert!(matches!(*circuit.error.borrow(), Some(FiboError::ZeroValue)));
+    }
+}
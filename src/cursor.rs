@@ -0,0 +1,117 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*
+};
+
+use crate::fibonacci::ACell;
+
+// A region plus the row we're on, for regions that fill rows one after the other. Everything
+// goes to the current row until next_row, so there are no offsets to get wrong
+pub struct RowCursor<'r, 'a, F: FieldExt> {
+    region: &'r mut Region<'a, F>,
+    offset: usize,
+}
+
+impl<'r, 'a, F: FieldExt> RowCursor<'r, 'a, F> {
+    pub fn new(region: &'r mut Region<'a, F>) -> Self {
+        Self { region, offset: 0 }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn next_row(&mut self) {
+        self.offset += 1;
+    }
+
+    pub fn enable(&mut self, selector: Selector) -> Result<(), Error> {
+        selector.enable(self.region, self.offset)
+    }
+
+    pub fn assign(&mut self, name: &str, column: Column<Advice>, value: Option<F>) -> Result<ACell<F>, Error> {
+        self.region.assign_advice(|| name, column, self.offset, || value.ok_or(Error::Synthesis)).map(ACell)
+    }
+
+    pub fn assign_fixed(&mut self, name: &str, column: Column<Fixed>, value: F) -> Result<(), Error> {
+        self.region.assign_fixed(|| name, column, self.offset, || Ok(value))?;
+        Ok(())
+    }
+
+    pub fn copy(&mut self, name: &str, column: Column<Advice>, cell: &ACell<F>) -> Result<ACell<F>, Error> {
+        cell.0.copy_advice(|| name, self.region, column, self.offset).map(ACell)
+    }
+
+    // for the calls the cursor doesn't wrap, constrain_equal and the like
+    pub fn region(&mut self) -> &mut Region<'a, F> {
+        self.region
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::Fp, poly::Rotation};
+
+    use super::*;
+
+    const ROWS: usize = 4;
+
+    // Assigns 1, 2, 3, 4 (advice) and 10, 20, 30, 40 (fixed) through the cursor, a gate at
+    // row 0 checks each one at its rotation. `stall` leaves out the next_row before that row
+    #[derive(Default)]
+    struct CursorCircuit {
+        stall: Option<usize>,
+    }
+
+    impl Circuit<Fp> for CursorCircuit {
+        type Config = (Column<Advice>, Column<Fixed>, Selector);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { stall: self.stall }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let (advice, fixed, select) = (meta.advice_column(), meta.fixed_column(), meta.selector());
+            meta.create_gate("offsets", |meta| {
+                let s = meta.query_selector(select);
+                (0..ROWS as i32).flat_map(|i| {
+                    let expected = Expression::Constant(Fp::from(i as u64 + 1));
+                    let v = meta.query_advice(advice, Rotation(i));
+                    let f = meta.query_fixed(fixed, Rotation(i));
+                    [s.clone() * (v - expected.clone()), s.clone() * (f - expected * Expression::Constant(Fp::from(10)))]
+                }).collect::<Vec<_>>()
+            });
+            (advice, fixed, select)
+        }
+
+        fn synthesize(&self, (advice, fixed, select): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            layouter.assign_region(|| "cursor", |mut region| {
+                let mut cursor = RowCursor::new(&mut region);
+                cursor.enable(select)?;
+                for i in 0..ROWS {
+                    if i > 0 && self.stall != Some(i) {
+                        cursor.next_row();
+                    }
+                    let value = i as u64 + 1;
+                    cursor.assign("v", advice, Some(Fp::from(value)))?;
+                    cursor.assign_fixed("f", fixed, Fp::from(10 * value))?;
+                }
+                assert_eq!(cursor.offset(), if self.stall.is_some() { ROWS - 2 } else { ROWS - 1 });
+                Ok(())
+            })
+        }
+    }
+
+    #[test]
+    fn rows_land_at_their_offsets() {
+        crate::assert_circuit!(CursorCircuit::default(), 4, vec![]);
+    }
+
+    #[test]
+    fn a_missed_next_row_shifts_the_rest() {
+        crate::assert_circuit_fails!(CursorCircuit { stall: Some(2) }, 4, vec![]);
+    }
+}
@@ -0,0 +1,483 @@
+use std::{env, fs, path::Path};
+
+use halo2_proofs::{
+    arithmetic::{CurveAffine, FieldExt},
+    dev::{MockProver, VerifyFailure},
+    pasta::{EqAffine, Fp},
+    plonk::{Circuit, VerifyingKey},
+    poly::commitment::Params,
+};
+
+use crate::{
+    error::FiboError,
+    fibonacci::FibonacciCircuit,
+    inspect::region_starts,
+    prover::{keygen, prove, verify, vk_bytes, ProofRng},
+};
+
+// Largest k find_min_k will try, MockProver gets slow well before this
+pub const MAX_K: u32 = 20;
+
+// Smallest k for which the circuit fits, by trying them in order. Only the layout has to fit,
+// whether the witness satisfies the constraints is a separate question (`verify()`).
+// Saves keeping a row count formula in sync with the gates, for chips without one.
+// Panics if nothing up to MAX_K fits
+pub fn find_min_k<F: FieldExt, C: Circuit<F>>(circuit_builder: impl Fn() -> (C, Vec<Vec<F>>)) -> u32 {
+    (1..=MAX_K)
+        .find(|&k| {
+            let (circuit, instances) = circuit_builder();
+            MockProver::run(k, &circuit, instances).is_ok()
+        })
+        .unwrap_or_else(|| panic!("circuit does not fit in 2^{} rows", MAX_K))
+}
+
+// MockProver run and verify without unwrap or assert_satisfied, for library callers. A
+// synthesis error (k too small, a missing witness) comes back as Halo2, failed constraints
+// as Unsatisfied with every failure verify() found
+pub fn try_mock_prove<F: FieldExt, C: Circuit<F>>(k: u32, circuit: &C, public: Vec<Vec<F>>) -> Result<(), FiboError> {
+    MockProver::run(k, circuit, public)?.verify().map_err(FiboError::Unsatisfied)
+}
+
+// Multi line report of what verify() returned, one numbered entry per failure with the gate,
+// the region and offset in it, and (for gates) the values of the cells it queried
+pub fn format_failures(failures: &[VerifyFailure]) -> String {
+    let mut report = format!("{} failure(s)\n", failures.len());
+    for (i, failure) in failures.iter().enumerate() {
+        let text = failure.to_string();
+        let mut lines = text.lines();
+        report += &format!("[{}] {}\n", i + 1, lines.next().unwrap_or_default());
+        for line in lines {
+            report += &format!("    {}\n", line);
+        }
+    }
+    report
+}
+
+// The first gate a witness breaks, narrowed down to the row and the cell values that break it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailureContext {
+    // e.g. "Constraint 0 in gate 0 ('add')"
+    pub constraint: String,
+    // e.g. "Region 5 ('next row')"
+    pub region: String,
+    pub offset: usize,
+    // row of the whole circuit, the region's first row plus the offset
+    pub row: usize,
+    // each cell the constraint queries (column and rotation) with its value, in query order
+    pub cells: Vec<(String, String)>,
+}
+
+// The first unsatisfied gate constraint of a circuit, None if no gate fails (the circuit
+// may still fail its copy constraints or lookups) or synthesis does. halo2 0.1 keeps the failure fields
+// private, so this is read back from the failure's Display output, and the region's row
+// from a dry run of the floor planner
+pub fn minimize_failure<F: FieldExt, C: Circuit<F>>(circuit: &C, k: u32, public: Vec<Vec<F>>) -> Option<FailureContext> {
+    let failures = MockProver::run(k, circuit, public).ok()?.verify().err()?;
+    let text = failures.iter()
+        .find(|failure| matches!(failure, VerifyFailure::ConstraintNotSatisfied { .. }))?
+        .to_string();
+    let mut lines = text.lines();
+
+    // "<constraint> is not satisfied in <region> at offset <offset>"
+    let (constraint, location) = lines.next()?.split_once(" is not satisfied in ")?;
+    let (region, offset) = location.rsplit_once(" at offset ")?;
+    let offset: usize = offset.trim().parse().ok()?;
+    let index: usize = region.strip_prefix("Region ")?.split(' ').next()?.parse().ok()?;
+    let start = region_starts(circuit).get(index).copied().flatten()?;
+
+    let cells = lines
+        .filter_map(|line| line.strip_prefix("- ")?.rsplit_once(" = "))
+        .map(|(cell, value)| (cell.to_string(), value.to_string()))
+        .collect();
+
+    Some(FailureContext {
+        constraint: constraint.to_string(),
+        region: region.to_string(),
+        offset,
+        row: start + offset,
+        cells,
+    })
+}
+
+// Circuits that know their own public inputs, so callers never compute them by hand
+pub trait SelfVerifying<F: FieldExt>: Circuit<F> + Sized {
+    fn expected_public(&self) -> Vec<Vec<F>>;
+
+    // Panics with the failures if the circuit isn't satisfied
+    fn mock_prove(&self, k: u32) {
+        let prover = MockProver::run(k, self, self.expected_public()).unwrap();
+        prover.assert_satisfied();
+    }
+}
+
+// Runs the circuit through MockProver and through keygen, a real proof and its verification,
+// and panics unless both paths agree. Returns whether they both succeeded. A synthesis error
+// counts as a failure on either side. Known ways the two can disagree:
+// - a cell the gates query but nothing assigns is an error to MockProver, the real prover
+//   just takes it as 0, so a gate that holds at 0 only passes there
+// - keygen runs without_witnesses, so a layout that depends on the witness breaks only the
+//   real path (the vk no longer matches the proof)
+// - MockProver checks instance values against the constrained cells, the real path only
+//   sees the public inputs it's given, which is the same as long as `public` is complete
+pub fn full_roundtrip<C: Circuit<Fp> + Clone>(circuit: &C, k: u32, public: Vec<Fp>) -> bool {
+    let mock = MockProver::run(k, circuit, vec![public.clone()]).map(|prover| prover.verify());
+    let mock_ok = matches!(mock, Ok(Ok(())));
+
+    let params = Params::<EqAffine>::new(k);
+    let real = keygen(&params, circuit).and_then(|pk| {
+        let proof = prove(&params, &pk, circuit.clone(), &public, ProofRng::seeded(0))?;
+        verify(&params, pk.get_vk(), &public, &proof)
+    });
+
+    assert!(mock_ok == real.is_ok(), "MockProver {} but the real proof {}",
+        if mock_ok { "passes" } else { "fails" },
+        match &real { Ok(()) => "verifies".to_string(), Err(e) => format!("fails ({:?})", e) });
+    mock_ok
+}
+
+// Committed snapshot of the f(9) verifying key, see `assert_fibonacci_vk_snapshot`
+pub const FIBONACCI_VK_SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/fibonacci_9.vk");
+
+// Panics if the vk_bytes of `vk` differ from the snapshot at `path`. After an intentional
+// change run it again with UPDATE_SNAPSHOTS=1, which rewrites the snapshot instead.
+// The snapshot is the Debug text of the pinned key (see vk_bytes), not a serialized key,
+// so a halo2 upgrade that formats it differently needs an update as well
+pub fn assert_vk_snapshot<C: CurveAffine>(vk: &VerifyingKey<C>, path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let bytes = vk_bytes(vk);
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(path, &bytes).unwrap_or_else(|e| panic!("could not write {}: {}", path.display(), e));
+        return;
+    }
+    let snapshot = fs::read(path).unwrap_or_else(|e| panic!("could not read {}: {}", path.display(), e));
+    assert!(snapshot == bytes, "verifying key differs from {}, UPDATE_SNAPSHOTS=1 if that's intended", path.display());
+}
+
+// Catches layout changes that would invalidate keys already handed out
+pub fn assert_fibonacci_vk_snapshot() {
+    let circuit = FibonacciCircuit::<Fp>::new(None, None, 9);
+    let params = Params::<EqAffine>::new(circuit.min_k());
+    let pk = keygen(&params, &circuit).expect("keygen failed");
+    assert_vk_snapshot(pk.get_vk(), FIBONACCI_VK_SNAPSHOT);
+}
+
+// MockProver run plus assert_satisfied, `assert_circuit!(circuit, k, vec![public])`
+#[macro_export]
+macro_rules! assert_circuit {
+    ($circuit:expr, $k:expr, $public:expr $(,)?) => {
+        $crate::prelude::MockProver::run($k, &$circuit, $public)
+            .expect("synthesis failed")
+            .assert_satisfied()
+    };
+}
+
+// The circuit has to fail verify(). With patterns, every failure has to match one of them:
+// `assert_circuit_fails!(circuit, k, public, VerifyFailure::Permutation { .. })`
+#[macro_export]
+macro_rules! assert_circuit_fails {
+    ($circuit:expr, $k:expr, $public:expr $(,)?) => {
+        assert!(
+            $crate::prelude::MockProver::run($k, &$circuit, $public)
+                .expect("synthesis failed")
+                .verify()
+                .is_err(),
+            "circuit is satisfied"
+        )
+    };
+    ($circuit:expr, $k:expr, $public:expr, $($kind:pat_param)|+ $(,)?) => {{
+        let failures = $crate::prelude::MockProver::run($k, &$circuit, $public)
+            .expect("synthesis failed")
+            .verify()
+            .expect_err("circuit is satisfied");
+        for failure in &failures {
+            assert!(matches!(failure, $($kind)|+), "unexpected failure: {}", failure);
+        }
+    }};
+}
+
+// An honest first row with the seeds 1, 1 exposed, then a row whose add is 1 + 1 = 3.
+// The failing circuit for the tests of dev and prover
+#[cfg(test)]
+#[derive(Clone)]
+pub(crate) struct BrokenAdd;
+
+#[cfg(test)]
+impl Circuit<Fp> for BrokenAdd {
+    type Config = crate::fibonacci::FiboConfig;
+
+    type FloorPlanner = halo2_proofs::circuit::SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self
+    }
+
+    fn configure(meta: &mut halo2_proofs::plonk::ConstraintSystem<Fp>) -> Self::Config {
+        FibonacciCircuit::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl halo2_proofs::circuit::Layouter<Fp>) -> Result<(), halo2_proofs::plonk::Error> {
+        let chip = crate::fibonacci::FiboChip::construct(config.clone());
+        let (a, b, _, _) = chip.assign_first_row(layouter.namespace(|| "first row"), Some(Fp::one()), Some(Fp::one()))?;
+        chip.expose_public(layouter.namespace(|| "a"), &a, 0)?;
+        chip.expose_public(layouter.namespace(|| "b"), &b, 1)?;
+        layouter.assign_region(|| "broken row", |mut region| {
+            config.select.enable(&mut region, 0)?;
+            for (column, value) in [(config.advice[0], 1), (config.advice[1], 1), (config.advice[2], 3),
+                (config.active[0], 1), (config.active[1], 1)] {
+                region.assign_advice(|| "value", column, 0, || Ok(Fp::from(value)))?;
+            }
+            region.assign_fixed(|| "pinned", config.pinned, 0, || Ok(Fp::one()))?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+impl SelfVerifying<Fp> for BrokenAdd {
+    fn expected_public(&self) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::one(), Fp::one()]]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        plonk::{ConstraintSystem, Error},
+    };
+
+    use super::*;
+    use crate::{compact::CompactFibonacciCircuit, fibonacci::{cell_value, ACell, FiboChip, FiboConfig}};
+
+    fn unit(steps: usize) -> FibonacciCircuit<Fp> {
+        FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), steps)
+    }
+
+    #[test]
+    fn find_min_k_fits_the_trace() {
+        let min_k = |steps| find_min_k(|| (unit(steps), unit(steps).expected_public()));
+        assert_eq!(min_k(9), 4);
+        assert!(min_k(30) > 4);
+        assert_eq!(min_k(30), unit(30).min_k());
+    }
+
+    // Differential check of the two Fibonacci implementations: the compact circuit has to
+    // accept the public inputs (seeds and output) of the three column one, for the same steps
+    // and each at its own smallest k
+    #[test]
+    fn implementations_agree() {
+        for (a, b) in [(0, 1), (1, 1), (2, 3), (1000, 7)] {
+            for steps in [0, 1, 2, 9, 30] {
+                let (a, b) = (Fp::from(a), Fp::from(b));
+                let fibo = FibonacciCircuit::new(Some(a), Some(b), steps);
+                let public = fibo.expected_public();
+                fibo.mock_prove(fibo.min_k());
+
+                let compact = CompactFibonacciCircuit::new(Some(a), Some(b), steps);
+                let k = find_min_k(|| (compact.clone(), public.clone()));
+                MockProver::run(k, &compact, public).unwrap().assert_satisfied();
+            }
+        }
+    }
+
+    #[test]
+    fn fibonacci_vk_matches_the_snapshot() {
+        assert_fibonacci_vk_snapshot();
+    }
+
+    #[test]
+    fn snapshot_catches_a_layout_change() {
+        // against a copy, so UPDATE_SNAPSHOTS can't overwrite the real one with this layout
+        let path = env::temp_dir().join(format!("halo2-circuits-snapshot-{}.vk", std::process::id()));
+        fs::copy(FIBONACCI_VK_SNAPSHOT, &path).unwrap();
+        let params = Params::<EqAffine>::new(4);
+        let vk = keygen(&params, &unit(9).with_difference_check()).unwrap().get_vk().clone();
+        let result = std::panic::catch_unwind(|| assert_vk_snapshot(&vk, &path));
+        fs::remove_file(&path).unwrap();
+        // the difference check adds a gate and two columns
+        assert!(result.is_err() || env::var_os("UPDATE_SNAPSHOTS").is_some());
+    }
+
+    #[test]
+    fn mock_prove_derives_the_public_inputs() {
+        // never spelled out: f(9) = 55
+        unit(9).mock_prove(4);
+        CompactFibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9).mock_prove(4);
+    }
+
+    #[test]
+    #[should_panic(expected = "circuit was not satisfied")]
+    fn mock_prove_panics_on_a_failure() {
+        // f(9), claiming the output is 56
+        struct Lying(FibonacciCircuit<Fp>);
+
+        impl Circuit<Fp> for Lying {
+            type Config = <FibonacciCircuit<Fp> as Circuit<Fp>>::Config;
+
+            type FloorPlanner = <FibonacciCircuit<Fp> as Circuit<Fp>>::FloorPlanner;
+
+            fn without_witnesses(&self) -> Self {
+                Lying(self.0.without_witnesses())
+            }
+
+            fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+                FibonacciCircuit::configure(meta)
+            }
+
+            fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+                self.0.synthesize(config, layouter)
+            }
+        }
+
+        impl SelfVerifying<Fp> for Lying {
+            fn expected_public(&self) -> Vec<Vec<Fp>> {
+                let mut public = self.0.expected_public();
+                *public[0].last_mut().unwrap() += Fp::one();
+                public
+            }
+        }
+
+        Lying(unit(9)).mock_prove(4);
+    }
+
+    #[test]
+    fn format_failures_names_the_gate_and_row() {
+        let failures = MockProver::run(4, &BrokenAdd, BrokenAdd.expected_public()).unwrap().verify().unwrap_err();
+        let report = format_failures(&failures);
+        assert!(report.starts_with("1 failure(s)\n[1] "), "{}", report);
+        assert!(report.contains("('add')"), "{}", report);
+        // the row after the first one, with the c it got
+        assert!(report.contains("Region 1 ('broken row') at offset 0"), "{}", report);
+        assert!(report.contains("Column('Advice', 2)@0 = 0x3"), "{}", report);
+    }
+
+    #[test]
+    fn try_mock_prove_returns_instead_of_panicking() {
+        assert!(try_mock_prove(4, &unit(9), unit(9).expected_public()).is_ok());
+
+        let wrong = vec![vec![Fp::one(), Fp::one(), Fp::from(56)]];
+        match try_mock_prove(4, &unit(9), wrong) {
+            Err(FiboError::Unsatisfied(failures)) =>
+                assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. }))),
+            other => panic!("expected Unsatisfied, got {:?}", other),
+        }
+        // 30 steps don't fit in k = 4
+        assert!(matches!(try_mock_prove(4, &unit(30), unit(30).expected_public()), Err(FiboError::Halo2(_))));
+    }
+
+    #[test]
+    fn roundtrip_agrees_on_a_satisfied_circuit() {
+        assert!(full_roundtrip(&unit(9), 4, unit(9).expected_public().remove(0)));
+    }
+
+    #[test]
+    fn roundtrip_agrees_on_an_unsatisfied_circuit() {
+        // a wrong output only breaks the copy to the instance, the broken add a gate
+        assert!(!full_roundtrip(&unit(9), 4, vec![Fp::one(), Fp::one(), Fp::from(56)]));
+        assert!(!full_roundtrip(&BrokenAdd, 4, BrokenAdd.expected_public().remove(0)));
+    }
+
+    #[test]
+    fn assert_circuit_accepts_a_satisfied_circuit() {
+        crate::assert_circuit!(unit(9), 4, unit(9).expected_public());
+    }
+
+    #[test]
+    #[should_panic(expected = "circuit was not satisfied")]
+    fn assert_circuit_panics_on_a_failure() {
+        crate::assert_circuit!(BrokenAdd, 4, BrokenAdd.expected_public());
+    }
+
+    #[test]
+    fn assert_circuit_fails_accepts_a_failure() {
+        crate::assert_circuit_fails!(BrokenAdd, 4, BrokenAdd.expected_public());
+        crate::assert_circuit_fails!(BrokenAdd, 4, BrokenAdd.expected_public(), VerifyFailure::ConstraintNotSatisfied { .. });
+        crate::assert_circuit_fails!(BrokenAdd, 4, BrokenAdd.expected_public(),
+            VerifyFailure::Lookup { .. } | VerifyFailure::ConstraintNotSatisfied { .. });
+    }
+
+    #[test]
+    #[should_panic(expected = "circuit is satisfied")]
+    fn assert_circuit_fails_panics_on_a_satisfied_circuit() {
+        crate::assert_circuit_fails!(unit(9), 4, unit(9).expected_public());
+    }
+
+    #[test]
+    #[should_panic(expected = "circuit is satisfied")]
+    fn assert_circuit_fails_with_a_pattern_panics_on_a_satisfied_circuit() {
+        crate::assert_circuit_fails!(unit(9), 4, unit(9).expected_public(), VerifyFailure::Permutation { .. });
+    }
+
+    #[test]
+    #[should_panic(expected = "unexpected failure")]
+    fn assert_circuit_fails_panics_on_the_wrong_kind() {
+        // the broken add is a gate failure, not a copy constraint
+        crate::assert_circuit_fails!(BrokenAdd, 4, BrokenAdd.expected_public(), VerifyFailure::Permutation { .. });
+    }
+
+    // f(9) with the add of row 5, f(5) + f(6) = f(7), off by one. Everything after it is
+    // computed honestly from the wrong value
+    struct BrokenF9;
+
+    impl Circuit<Fp> for BrokenF9 {
+        type Config = FiboConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = FiboChip::construct(config.clone());
+            let (_, mut b, mut c, mut active) = chip.assign_first_row(layouter.namespace(|| "first row"), Some(Fp::one()), Some(Fp::one()))?;
+            for row in 1..8 {
+                let (next_c, next_active) = if row == 5 {
+                    layouter.assign_region(|| "broken row", |mut region| {
+                        config.select.enable(&mut region, 0)?;
+                        b.0.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                        c.0.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+                        active.0.copy_advice(|| "prev active", &mut region, config.active[1], 0)?;
+                        region.assign_fixed(|| "pinned", config.pinned, 0, || Ok(Fp::one()))?;
+                        let sum = cell_value(&b).zip(cell_value(&c)).map(|(b, c)| b + c + Fp::one());
+                        let next_c = region.assign_advice(|| "c", config.advice[2], 0, || sum.ok_or(Error::Synthesis)).map(ACell)?;
+                        let next_active = region.assign_advice(|| "active", config.active[0], 0, || Ok(Fp::one())).map(ACell)?;
+                        Ok((next_c, next_active))
+                    })?
+                } else {
+                    chip.assign_row(layouter.namespace(|| "next row"), &b, &c, &active, true)?
+                };
+                b = c;
+                c = next_c;
+                active = next_active;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn minimize_failure_finds_the_broken_add() {
+        let context = minimize_failure(&BrokenF9, 4, vec![vec![]]).expect("the add gate fails");
+        assert_eq!(context.constraint, "Constraint 0 in gate 0 ('add')");
+        assert!(context.region.ends_with("('broken row')"), "{}", context.region);
+        assert_eq!((context.offset, context.row), (0, 5));
+        // a = f(5) = 8, b = f(6) = 13, c = 22 instead of 21, active
+        let values: Vec<_> = context.cells.iter().map(|(_, value)| value.as_str()).collect();
+        assert_eq!(values, ["0x8", "0xd", "0x16", "1"]);
+    }
+
+    #[test]
+    fn minimize_failure_is_none_for_a_satisfied_circuit() {
+        assert_eq!(minimize_failure(&unit(9), 4, unit(9).expected_public()), None);
+    }
+
+    #[test]
+    fn minimize_failure_is_none_when_synthesis_fails() {
+        // 30 steps don't fit in k = 4
+        assert_eq!(minimize_failure(&unit(30), 4, unit(30).expected_public()), None);
+    }
+}
@@ -0,0 +1,192 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    bits::BitsChip,
+    divmod::{DivModChip, DivModConfig},
+    dev::find_min_k,
+    fibonacci::{cell_value, ACell},
+    layouter_ext::LayouterExt,
+    linear::LinearChip,
+};
+
+// Bits of a digit and of 9 - digit, both fitting means the digit is 0..=9
+const DIGIT_BITS: usize = 4;
+
+// Casting out nines: x mod 9 is the digit sum mod 9, since 10 = 1 mod 9. Each witnessed
+// digit is range checked to 0..=9, x = sum(10^i * d_i) and s = sum(d_i) are linear
+// combinations, and divmod by 9 of both has to leave the same remainder. x goes through
+// divmod too, so it has to fit in DIVMOD_BITS
+#[derive(Debug, Clone)]
+pub struct DigitSumConfig {
+    pub divmod: DivModConfig,
+}
+
+pub struct DigitSumChip<F: FieldExt> {
+    config: DigitSumConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DigitSumChip<F> {
+    pub fn construct(config: DigitSumConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> DigitSumConfig {
+        DigitSumConfig { divmod: DivModChip::configure(meta) }
+    }
+
+    // Digits lowest first, at most 19 so their value stays a u64. Returns x mod 9
+    pub fn digit_sum_check(&self, mut layouter: impl Layouter<F>, x: &ACell<F>, digits: &[ACell<F>]) -> Result<ACell<F>, Error> {
+        assert!((1..=19).contains(&digits.len()), "1 to 19 decimal digits");
+        let [col_digit, col_nine, col_s] = self.config.divmod.mul.advice;
+
+        let bits = BitsChip::construct(self.config.divmod.bits.clone());
+        let linear = LinearChip::construct(self.config.divmod.linear.clone());
+        for digit in digits {
+            let rest = layouter.assign_value("9 - digit", col_digit, cell_value(digit).map(|d| F::from(9) - d))?;
+            bits.decompose(layouter.namespace(|| "digit range"), digit, DIGIT_BITS)?;
+            bits.decompose(layouter.namespace(|| "9 - digit range"), &rest, DIGIT_BITS)?;
+            linear.linear_combination_eq(layouter.namespace(|| "digit + rest = 9"), &[digit.clone(), rest],
+                &[F::one(), F::one()], F::from(9))?;
+        }
+
+        // x = sum(10^i * d_i)
+        let mut cells = digits.to_vec();
+        cells.push(x.clone());
+        let mut coeffs: Vec<F> = std::iter::successors(Some(F::one()), |&c| Some(c * F::from(10))).take(digits.len()).collect();
+        coeffs.push(-F::one());
+        linear.linear_combination_eq(layouter.namespace(|| "x = digits"), &cells, &coeffs, F::zero())?;
+
+        // s = sum(d_i)
+        let s_val = digits.iter().try_fold(F::zero(), |s, digit| cell_value(digit).map(|d| s + d));
+        let s = layouter.assign_value("digit sum", col_s, s_val)?;
+        cells[digits.len()] = s.clone();
+        let mut coeffs = vec![F::one(); digits.len()];
+        coeffs.push(-F::one());
+        linear.linear_combination_eq(layouter.namespace(|| "s = sum of digits"), &cells, &coeffs, F::zero())?;
+
+        // the mul chip's constant column takes the divisor
+        let nine = layouter.assign_region(|| "nine", |mut region| {
+            region.assign_advice_from_constant(|| "nine", col_nine, 0, F::from(9)).map(ACell)
+        })?;
+        let divmod = DivModChip::construct(self.config.divmod.clone());
+        let (_, x_mod) = divmod.divmod(layouter.namespace(|| "x mod 9"), x, &nine)?;
+        let (_, s_mod) = divmod.divmod(layouter.namespace(|| "s mod 9"), &s, &nine)?;
+        layouter.assign_region(|| "x mod 9 = s mod 9", |mut region| {
+            let s_mod = s_mod.0.copy_advice(|| "s mod 9", &mut region, col_s, 0)?;
+            region.constrain_equal(s_mod.cell(), x_mod.0.cell())
+        })?;
+
+        Ok(x_mod)
+    }
+}
+
+// Decimal digits of x, lowest first, a single 0 for x = 0
+pub fn decimal_digits(x: u64) -> Vec<u64> {
+    let mut digits = vec![x % 10];
+    let mut rest = x / 10;
+    while rest > 0 {
+        digits.push(rest % 10);
+        rest /= 10;
+    }
+    digits
+}
+
+// Digit sum repeated until one digit is left, 9 for nonzero multiples of 9 where x mod 9 is 0
+pub fn digital_root(x: u64) -> u64 {
+    if x == 0 { 0 } else { 1 + (x - 1) % 9 }
+}
+
+#[derive(Debug, Clone)]
+pub struct DigitSumCircuitConfig {
+    pub digit_sum: DigitSumConfig,
+    pub instance: Column<Instance>,
+}
+
+// Public x at instance row 0 and x mod 9 at row 1, the digits private. The number of
+// digits is part of the shape
+#[derive(Default, Clone)]
+pub struct DigitSumCircuit<F> {
+    pub len: usize,
+    pub digits: Option<Vec<u64>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DigitSumCircuit<F> {
+    pub fn new(x: u64) -> Self {
+        Self::with_digits(decimal_digits(x))
+    }
+
+    // Any digits, for checking the circuit rejects ones that don't form x
+    pub fn with_digits(digits: Vec<u64>) -> Self {
+        Self { len: digits.len(), digits: Some(digits), _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for DigitSumCircuit<F> {
+    type Config = DigitSumCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { digits: None, ..self.clone() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        DigitSumCircuitConfig { digit_sum: DigitSumChip::configure(meta), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let [col_x, col_digit, _] = config.digit_sum.divmod.mul.advice;
+        let x = layouter.assign_from_instance("x", config.instance, 0, col_x)?;
+        let digits = (0..self.len)
+            .map(|i| layouter.assign_value("digit", col_digit, self.digits.as_ref().map(|digits| F::from(digits[i]))))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let x_mod = DigitSumChip::construct(config.digit_sum).digit_sum_check(layouter.namespace(|| "digit sum"), &x, &digits)?;
+        layouter.constrain_instance(x_mod.0.cell(), config.instance, 1)
+    }
+}
+
+// Mock proves x mod 9 from the given digits of x
+pub fn prove_digit_sum(x: u64, digits: Vec<u64>) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = DigitSumCircuit::<Fp>::with_digits(digits);
+    let public = vec![vec![Fp::from(x), Fp::from(x % 9)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn casts_out_nines_from_55() {
+        // 5 + 5 = 10, 1 + 0 = 1 and 55 = 6 * 9 + 1
+        assert_eq!((decimal_digits(55), digital_root(55)), (vec![5, 5], 1));
+        assert_eq!(prove_digit_sum(55, decimal_digits(55)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_digits_that_dont_form_x() {
+        // 64 has the same digit sum mod 9 as 55, but isn't 55
+        let failures = prove_digit_sum(55, vec![4, 6]).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+
+    #[test]
+    fn rejects_a_digit_past_9() {
+        // 15 + 10 * 4 = 55, but 15 is no decimal digit
+        assert!(prove_digit_sum(55, vec![15, 4]).is_err());
+    }
+}
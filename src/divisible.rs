@@ -0,0 +1,139 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    error::FiboError,
+    fibonacci::{cell_value, ACell},
+    mul::{MulChip, MulConfig},
+};
+
+// Width the quotient is range checked to, x is read as an integer below 2^QUOTIENT_BITS
+pub const QUOTIENT_BITS: usize = 64;
+
+// d | x for a public constant d, by witnessing q with q * d = x on the mul gate.
+// In a field every x is a multiple of every nonzero d, so q is also range checked:
+// with q < 2^64 and d < 2^64 the product can't wrap around the modulus
+#[derive(Debug, Clone)]
+pub struct DivisibleConfig {
+    pub mul: MulConfig,
+    pub bits: BitsConfig,
+}
+
+pub struct DivisibleChip<F: FieldExt> {
+    config: DivisibleConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> DivisibleChip<F> {
+    pub fn construct(config: DivisibleConfig) -> Self {
+        Self { config, _marker: std::marker::PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> DivisibleConfig {
+        DivisibleConfig {
+            mul: MulChip::configure(meta),
+            bits: BitsChip::configure(meta),
+        }
+    }
+
+    pub fn assert_divisible(&self, mut layouter: impl Layouter<F>, x: &ACell<F>, d: u64) -> Result<(), FiboError> {
+        if d == 0 {
+            return Err(FiboError::ZeroDivisor);
+        }
+
+        let mul = &self.config.mul;
+        let q = layouter.assign_region(|| "divisible", |mut region| {
+            mul.select.enable(&mut region, 0)?;
+
+            // the honest quotient, which only satisfies q * d = x when there is no remainder
+            let q_val = cell_value(x).map(|x| F::from_u128(x.get_lower_128() / d as u128));
+            let q = region.assign_advice(|| "q", mul.advice[0], 0,
+                || q_val.ok_or(Error::Synthesis)).map(ACell)?;
+            region.assign_advice_from_constant(|| "d", mul.advice[1], 0, F::from(d))?;
+            x.0.copy_advice(|| "x", &mut region, mul.advice[2], 0)?;
+            Ok(q)
+        })?;
+
+        BitsChip::construct(self.config.bits.clone())
+            .decompose(layouter.namespace(|| "quotient range"), &q, QUOTIENT_BITS)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::{dev::full_roundtrip, layouter_ext::LayouterExt};
+
+    // d | x with x taken from instance row 0. Whatever assert_divisible returned is kept
+    // in `result`, halo2 only gets to see Error::Synthesis
+    struct DivisibleCircuit {
+        d: u64,
+        result: RefCell<Option<Result<(), FiboError>>>,
+    }
+
+    impl DivisibleCircuit {
+        fn new(d: u64) -> Self {
+            Self { d, result: RefCell::new(None) }
+        }
+    }
+
+    impl Clone for DivisibleCircuit {
+        fn clone(&self) -> Self {
+            Self::new(self.d)
+        }
+    }
+
+    impl Circuit<Fp> for DivisibleCircuit {
+        type Config = (DivisibleConfig, Column<Instance>);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (DivisibleChip::configure(meta), instance)
+        }
+
+        fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let x = layouter.assign_from_instance("x", instance, 0, config.mul.advice[2])?;
+            let result = DivisibleChip::construct(config).assert_divisible(layouter.namespace(|| "divisible"), &x, self.d);
+            let failed = result.is_err();
+            *self.result.borrow_mut() = Some(result);
+            if failed { Err(Error::Synthesis) } else { Ok(()) }
+        }
+    }
+
+    #[test]
+    fn divisible_by_5() {
+        assert!(full_roundtrip(&DivisibleCircuit::new(5), 8, vec![Fp::from(55)]));
+    }
+
+    #[test]
+    fn not_divisible_by_4() {
+        // the honest quotient 13 gives 13 * 4 = 52, and no quotient below 2^64 gives 55
+        let circuit = DivisibleCircuit::new(4);
+        crate::assert_circuit_fails!(circuit, 8, vec![vec![Fp::from(55)]], VerifyFailure::ConstraintNotSatisfied { .. });
+        assert!(!full_roundtrip(&circuit, 8, vec![Fp::from(55)]));
+    }
+
+    #[test]
+    fn rejects_a_zero_divisor() {
+        let circuit = DivisibleCircuit::new(0);
+        assert!(halo2_proofs::dev::MockProver::run(8, &circuit, vec![vec![Fp::from(55)]]).is_err());
+        assert!(matches!(circuit.result.borrow().as_ref(), Some(Err(FiboError::ZeroDivisor))));
+    }
+}
@@ -0,0 +1,231 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    dev::find_min_k,
+    error::FiboError,
+    fibonacci::{cell_value, ACell},
+    layouter_ext::LayouterExt,
+    linear::{LinearChip, LinearConfig},
+    mul::{MulChip, MulConfig},
+};
+
+// Width of a, b, q, r and d, so q * b + r stays far below the modulus
+pub const DIVMOD_BITS: usize = 64;
+
+// a = q * b + r with 0 <= r < b, for a and b cells. q * b is a mul gate, a - q * b - r = 0 and
+// r + d = b - 1 are linear combinations, and everything is range checked so they hold over
+// the integers. The d row is what rules out r >= b
+#[derive(Debug, Clone)]
+pub struct DivModConfig {
+    pub mul: MulConfig,
+    pub linear: LinearConfig,
+    pub bits: BitsConfig,
+}
+
+pub struct DivModChip<F: FieldExt> {
+    config: DivModConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DivModChip<F> {
+    pub fn construct(config: DivModConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> DivModConfig {
+        DivModConfig {
+            mul: MulChip::configure(meta),
+            linear: LinearChip::configure(meta),
+            bits: BitsChip::configure(meta),
+        }
+    }
+
+    // Returns (q, r)
+    pub fn divmod(&self, layouter: impl Layouter<F>, a: &ACell<F>, b: &ACell<F>) -> Result<(ACell<F>, ACell<F>), FiboError> {
+        let a_val = cell_value(a).map(|a| a.get_lower_128() as u64);
+        let b_val = cell_value(b).map(|b| b.get_lower_128() as u64);
+        // None for b = 0 too, divmod_with rejects that
+        let q = a_val.and_then(|a| b_val.and_then(|b| a.checked_div(b)));
+        let r = a_val.and_then(|a| b_val.and_then(|b| a.checked_rem(b)));
+        self.divmod_with(layouter, a, b, q, r)
+    }
+
+    // Same with q and r given instead of computed, for checking forged ones get rejected
+    pub fn divmod_with(&self, mut layouter: impl Layouter<F>, a: &ACell<F>, b: &ACell<F>,
+        q: Option<u64>, r: Option<u64>) -> Result<(ACell<F>, ACell<F>), FiboError> {
+        if cell_value(b) == Some(F::zero()) {
+            return Err(FiboError::ZeroDivisor);
+        }
+        let d = cell_value(b).and_then(|b| r.map(|r| b - F::one() - F::from(r)));
+
+        let [col_q, col_r, col_d] = self.config.mul.advice;
+        let (q, r, d) = layouter.assign_region(|| "divmod witness", |mut region| {
+            let q = region.assign_advice(|| "q", col_q, 0,
+                || q.map(F::from).ok_or(Error::Synthesis)).map(ACell)?;
+            let r = region.assign_advice(|| "r", col_r, 0,
+                || r.map(F::from).ok_or(Error::Synthesis)).map(ACell)?;
+            let d = region.assign_advice(|| "d", col_d, 0,
+                || d.ok_or(Error::Synthesis)).map(ACell)?;
+            Ok((q, r, d))
+        })?;
+
+        let bits = BitsChip::construct(self.config.bits.clone());
+        for (name, cell) in [("a range", a), ("b range", b), ("q range", &q), ("r range", &r), ("d range", &d)] {
+            bits.decompose(layouter.namespace(|| name), cell, DIVMOD_BITS)?;
+        }
+
+        let qb = MulChip::construct(self.config.mul.clone()).mul(layouter.namespace(|| "q * b"), &q, b)?;
+
+        let linear = LinearChip::construct(self.config.linear.clone());
+        linear.linear_combination_eq(layouter.namespace(|| "a = q * b + r"), &[a.clone(), qb, r.clone()],
+            &[F::one(), -F::one(), -F::one()], F::zero())?;
+        linear.linear_combination_eq(layouter.namespace(|| "r + d = b - 1"), &[r.clone(), d, b.clone()],
+            &[F::one(), F::one(), -F::one()], -F::one())?;
+
+        Ok((q, r))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DivModCircuitConfig {
+    pub divmod: DivModConfig,
+    pub instance: Column<Instance>,
+}
+
+// Public a and b at instance rows 0 and 1, q and r at rows 2 and 3. forged replaces the
+// honest (q, r), see `divmod_with`
+#[derive(Default, Clone)]
+pub struct DivModCircuit<F> {
+    pub forged: Option<(u64, u64)>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> DivModCircuit<F> {
+    pub fn new() -> Self {
+        Self { forged: None, _marker: PhantomData }
+    }
+
+    pub fn forged(q: u64, r: u64) -> Self {
+        Self { forged: Some((q, r)), ..Self::new() }
+    }
+
+    pub fn public(a: u64, b: u64, q: u64, r: u64) -> Vec<Vec<F>> {
+        vec![[a, b, q, r].map(F::from).to_vec()]
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for DivModCircuit<F> {
+    type Config = DivModCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        DivModCircuitConfig { divmod: DivModChip::configure(meta), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let [col_a, col_b, _] = config.divmod.mul.advice;
+        let a = layouter.assign_from_instance("a", config.instance, 0, col_a)?;
+        let b = layouter.assign_from_instance("b", config.instance, 1, col_b)?;
+
+        let chip = DivModChip::construct(config.divmod);
+        let (q, r) = match self.forged {
+            Some((q, r)) => chip.divmod_with(layouter.namespace(|| "divmod"), &a, &b, Some(q), Some(r))?,
+            None => chip.divmod(layouter.namespace(|| "divmod"), &a, &b)?,
+        };
+        layouter.constrain_instance(q.0.cell(), config.instance, 2)?;
+        layouter.constrain_instance(r.0.cell(), config.instance, 3)
+    }
+}
+
+// Mock proves a / b = (q, r) for the circuit's own (q, r), honest or forged
+pub fn prove_divmod(circuit: DivModCircuit<Fp>, a: u64, b: u64, q: u64, r: u64) -> Result<(), Vec<VerifyFailure>> {
+    let public = DivModCircuit::public(a, b, q, r);
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+
+    #[test]
+    fn divides_17_by_5() {
+        assert_eq!(prove_divmod(DivModCircuit::new(), 17, 5, 3, 2), Ok(()));
+    }
+
+    #[test]
+    fn divides_20_by_4_exactly() {
+        assert_eq!(prove_divmod(DivModCircuit::new(), 20, 4, 5, 0), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_remainder_past_the_divisor() {
+        // 17 = 2 * 5 + 7 holds, but 7 + d = 4 needs d = -3, whose bits don't add back up to it
+        let failures = prove_divmod(DivModCircuit::forged(2, 7), 17, 5, 2, 7).unwrap_err();
+        assert!(failures.iter().any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+
+    // divmod of instance rows 0 and 1, keeping what it returned
+    #[derive(Default)]
+    struct ZeroCheck {
+        result: RefCell<Option<FiboError>>,
+    }
+
+    impl Clone for ZeroCheck {
+        fn clone(&self) -> Self {
+            Self::default()
+        }
+    }
+
+    impl Circuit<Fp> for ZeroCheck {
+        type Config = DivModCircuitConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            DivModCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let [col_a, col_b, _] = config.divmod.mul.advice;
+            let a = layouter.assign_from_instance("a", config.instance, 0, col_a)?;
+            let b = layouter.assign_from_instance("b", config.instance, 1, col_b)?;
+            match DivModChip::construct(config.divmod).divmod(layouter.namespace(|| "divmod"), &a, &b) {
+                Ok(_) => Ok(()),
+                Err(e) => {
+                    *self.result.borrow_mut() = Some(e);
+                    Err(Error::Synthesis)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_a_zero_divisor_up_front() {
+        let circuit = ZeroCheck::default();
+        assert!(MockProver::run(8, &circuit, DivModCircuit::public(17, 0, 0, 0)).is_err());
+        assert!(matches!(circuit.result.borrow().as_ref(), Some(FiboError::ZeroDivisor)));
+    }
+}
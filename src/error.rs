@@ -0,0 +1,70 @@
+use std::fmt;
+
+use halo2_proofs::{dev::VerifyFailure, plonk::Error};
+
+use crate::dev::format_failures;
+
+// Errors of our own on top of halo2's, for inputs we can reject before touching the layouter
+#[derive(Debug)]
+pub enum FiboError {
+    Halo2(Error),
+    // d = 0 divides nothing (and every x = q * 0 check would be vacuous)
+    ZeroDivisor,
+    // 0 has no lowest set bit, so no trailing zero count
+    ZeroValue,
+    // proving failed (or gave a proof that doesn't verify), with what MockProver found
+    // wrong with the witness, see `prove_diagnosed`
+    Diagnosed(Error, Vec<VerifyFailure>),
+    // MockProver ran and found these, see `try_mock_prove`
+    Unsatisfied(Vec<VerifyFailure>),
+    // the trace row for f(step) is past the `available` usable rows of the chosen k,
+    // see `FibonacciCircuit::mock_run`
+    OutOfRows { step: usize, available: usize },
+    // output_at_step past the end of the trace
+    OutputPastTrace { step: usize, steps: usize },
+    // the check needs rows of its own but pad_to_k already took every usable row, so the
+    // two can't go in one circuit
+    PaddedCheck(&'static str),
+    // public inputs of the wrong length, see `reconstruct_trace`
+    PublicLength { expected: usize, found: usize },
+    // the public output is not f(steps) of the public seeds
+    OutputMismatch { steps: usize },
+}
+
+impl fmt::Display for FiboError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FiboError::Halo2(e) => write!(f, "{}", e),
+            FiboError::ZeroDivisor => write!(f, "divisor must be nonzero"),
+            FiboError::ZeroValue => write!(f, "value must be nonzero"),
+            FiboError::Diagnosed(e, failures) => write!(f, "{}, {}", e, format_failures(failures)),
+            FiboError::Unsatisfied(failures) => write!(f, "circuit not satisfied, {}", format_failures(failures)),
+            FiboError::OutOfRows { step, available } =>
+                write!(f, "out of rows at step {}, only {} usable rows", step, available),
+            FiboError::OutputPastTrace { step, steps } =>
+                write!(f, "f({}) is not in a trace of {} steps", step, steps),
+            FiboError::PaddedCheck(check) => write!(f, "no rows left for the {} in a padded circuit", check),
+            FiboError::PublicLength { expected, found } =>
+                write!(f, "expected {} public inputs, found {}", expected, found),
+            FiboError::OutputMismatch { steps } => write!(f, "public output is not f({}) of the seeds", steps),
+        }
+    }
+}
+
+impl std::error::Error for FiboError {}
+
+impl From<Error> for FiboError {
+    fn from(e: Error) -> Self {
+        FiboError::Halo2(e)
+    }
+}
+
+// synthesize has to return halo2's error, which can't carry ours
+impl From<FiboError> for Error {
+    fn from(e: FiboError) -> Self {
+        match e {
+            FiboError::Halo2(e) | FiboError::Diagnosed(e, _) => e,
+            _ => Error::Synthesis,
+        }
+    }
+}
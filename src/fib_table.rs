@@ -0,0 +1,118 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::fibonacci::ACell;
+
+// Lookup table with the first 2^BITS Fibonacci numbers (0, 1, 1, 2, ...).
+// Checking a value is a Fibonacci number is then one lookup, instead of recomputing the
+// sequence up to its index, as long as the range of indices is bounded
+#[derive(Debug, Clone)]
+pub struct FibTableConfig<F: FieldExt, const BITS: usize> {
+    pub advice: Column<Advice>,
+    pub table: TableColumn,
+    pub select: Selector,
+    _marker: PhantomData<F>,
+}
+
+pub struct FibTableChip<F: FieldExt, const BITS: usize> {
+    config: FibTableConfig<F, BITS>,
+}
+
+impl<F: FieldExt, const BITS: usize> FibTableChip<F, BITS> {
+    pub fn construct(config: FibTableConfig<F, BITS>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FibTableConfig<F, BITS> {
+        let advice = meta.advice_column();
+        let table = meta.lookup_table_column();
+        let selector = meta.complex_selector();
+
+        meta.enable_equality(advice);
+
+        // disabled rows look up 0, which is f(0) so always in the table
+        meta.lookup(|meta| {
+            let s = meta.query_selector(selector);
+            let x = meta.query_advice(advice, Rotation::cur());
+            vec![(s * x, table)]
+        });
+
+        FibTableConfig { advice, table, select: selector, _marker: PhantomData }
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "fibonacci table", |mut table| {
+            let (mut a, mut b) = (F::zero(), F::one());
+            for i in 0..(1 << BITS) {
+                table.assign_cell(|| "term", self.config.table, i, || Ok(a))?;
+                let c = a + b;
+                a = b;
+                b = c;
+            }
+            Ok(())
+        })
+    }
+
+    pub fn lookup_term(&self, mut layouter: impl Layouter<F>, x: &ACell<F>) -> Result<(), Error> {
+        layouter.assign_region(|| "lookup term", |mut region| {
+            self.config.select.enable(&mut region, 0)?;
+            x.0.copy_advice(|| "x", &mut region, self.config.advice, 0)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::layouter_ext::LayouterExt;
+
+    // Public x at instance row 0 has to be one of the first 16 terms
+    #[derive(Default)]
+    struct TermCircuit;
+
+    impl Circuit<Fp> for TermCircuit {
+        type Config = (FibTableConfig<Fp, 4>, Column<Instance>);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (FibTableChip::configure(meta), instance)
+        }
+
+        fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = FibTableChip::construct(config.clone());
+            chip.load(&mut layouter)?;
+            let x = layouter.assign_from_instance("x", instance, 0, config.advice)?;
+            chip.lookup_term(layouter.namespace(|| "x"), &x)
+        }
+    }
+
+    #[test]
+    fn finds_terms() {
+        // f(15) = 610 is the last term of the table
+        for x in [0, 1, 13, 610] {
+            crate::assert_circuit!(TermCircuit, 5, vec![vec![Fp::from(x)]]);
+        }
+    }
+
+    #[test]
+    fn rejects_other_values() {
+        for x in [4, 14, 987] {
+            crate::assert_circuit_fails!(TermCircuit, 5, vec![vec![Fp::from(x)]], VerifyFailure::Lookup { .. });
+        }
+    }
+}
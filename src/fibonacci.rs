@@ -0,0 +1,1180 @@
+use std::{cell::RefCell, marker::PhantomData};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::MockProver,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    dev::SelfVerifying,
+    error::FiboError,
+    instance_layout::InstanceLayout,
+    pool::ColumnPool,
+};
+
+#[derive(Debug, Clone)]
+pub struct ACell<F: FieldExt>(pub AssignedCell<F, F>);
+
+// Defines the configuration of all the columns, and all of the column definitions
+// Will be incrementally populated and passed around
+#[derive(Debug, Clone)]
+pub struct FiboConfig {
+    pub advice: [Column<Advice>; 3],
+    // active flag of this row and a copy of the previous row's flag, see `pad_to_k`
+    pub active: [Column<Advice>; 2],
+    // 1 on rows whose active flag is pinned to 1, see `assign_pinned_row`
+    pub pinned: Column<Fixed>,
+    pub select: Selector,
+    // optional backward difference check, see `difference_check`
+    pub diff_advice: [Column<Advice>; 2],
+    pub diff: Selector,
+    // optional parity check, on the trace's own columns, see `parity_check`
+    pub parity_bits: BitsConfig,
+    // advice columns nothing assigns to yet, see `RecursionConfig`
+    pub reserved: Vec<Column<Advice>>,
+    pub instance: Column<Instance>
+}
+
+// Knobs for the proving system side of the layout.
+// min_degree raises the degree the constraint system reports, which grows the extended
+// domain the prover works in (headroom for higher degree gates later on, same vk shape
+// otherwise). The number of blinding rows is not ours to pick in this halo2 version:
+// it follows from the most queried advice column, see ConstraintSystem::blinding_factors
+#[derive(Debug, Clone, Default)]
+pub struct ZkConfig {
+    pub min_degree: Option<usize>,
+}
+
+// Layout headroom for folding these proofs into a recursive one later on.
+// reserved_advice allocates advice columns that stay empty for now (room for an accumulator
+// or challenges), extra_blinding adds blinding rows on top of the ones halo2 already keeps.
+// Since the blinding rows follow the most queried advice column (see ZkConfig), the margin
+// comes from a gate that is never enabled and only queries the first reserved column at
+// enough rotations. Both change the vk, neither changes what is proven
+#[derive(Debug, Clone, Default)]
+pub struct RecursionConfig {
+    pub reserved_advice: usize,
+    pub extra_blinding: usize,
+}
+
+// The assigned value of a cell, None when there is no witness (e.g. during keygen)
+pub fn cell_value<F: FieldExt>(cell: &ACell<F>) -> Option<F> {
+    cell.0.value().copied()
+}
+
+// Advice columns the chip needs, the trace, the two flags and the difference check
+pub const FIBO_COLUMNS: usize = 7;
+
+// Width the terms are decomposed to for the parity check, they have to be smaller than 2^128
+pub const PARITY_BITS: usize = 128;
+
+// a, b, c and the active flag of the first row
+pub type FirstRow<F> = (ACell<F>, ACell<F>, ACell<F>, ACell<F>);
+
+pub struct FiboChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>, // no meaning here
+    // In rust, when you have a struct that is generic over a type parameter (here F),
+    // but the type parameter is not referenced in a field of the struct,
+    // you have to use PhantomData to virtually reference the type parameter,
+    // so that the compiler can track it.  Otherwise it would give an error. - Jason
+}
+
+impl<F: FieldExt> FiboChip<F> {
+    // Default constructor
+    pub fn construct(config: FiboConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    // Going to generate a circuit and define a custom gate here
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> FiboConfig {
+        Self::configure_zk(meta, instance, &ZkConfig::default())
+    }
+
+    pub fn configure_zk(meta: &mut ConstraintSystem<F>, instance: Column<Instance>, zk: &ZkConfig) -> FiboConfig {
+        if let Some(degree) = zk.min_degree {
+            meta.set_minimum_degree(degree);
+        }
+
+        let pool = ColumnPool::new(meta, FIBO_COLUMNS);
+        Self::configure_with_columns(meta, instance, &pool)
+    }
+
+    pub fn configure_recursion(meta: &mut ConstraintSystem<F>, instance: Column<Instance>, recursion: &RecursionConfig) -> FiboConfig {
+        let mut config = Self::configure(meta, instance);
+        config.reserved = (0..recursion.reserved_advice).map(|_| meta.advice_column()).collect();
+
+        if recursion.extra_blinding > 0 {
+            let column = *config.reserved.first().expect("the blinding margin needs a reserved column");
+            let margin = meta.selector();
+            // halo2 keeps max(3, queries of the most queried column) + 2 blinding rows
+            let queries = meta.blinding_factors() - 2 + recursion.extra_blinding;
+            meta.create_gate("blinding margin", |meta| {
+                let s = meta.query_selector(margin);
+                (0..queries)
+                    .map(|i| s.clone() * meta.query_advice(column, Rotation(i as i32)))
+                    .collect::<Vec<_>>()
+            });
+        }
+        config
+    }
+
+    // Same as configure, but on columns from a pool other chips may be using too
+    pub fn configure_with_columns(meta: &mut ConstraintSystem<F>, instance: Column<Instance>, pool: &ColumnPool) -> FiboConfig {
+        let [col_a, col_b, col_c, col_active, col_prev_active, col_u, col_v] = pool.advice();
+        let selector = meta.selector();
+        let diff = meta.selector();
+        // the first row pins its active flag to the constant 1
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        let pinned = meta.fixed_column();
+
+        // If you dont enable equality you cant do any permutation checks inside this column
+        meta.enable_equality(col_a);
+        meta.enable_equality(col_b);
+        meta.enable_equality(col_c);
+        meta.enable_equality(col_active);
+        meta.enable_equality(col_prev_active);
+        meta.enable_equality(col_u);
+        meta.enable_equality(col_v);
+        meta.enable_equality(instance);
+
+
+        meta.create_gate("add", |meta|{
+            // local gate constraint
+            // col_a | col_b | col_c | active | selector
+            //   a      b        c       t        s
+            //
+            // an active row adds, a padding row (t = 0) just carries b forward
+            let s = meta.query_selector(selector);
+            let a = meta.query_advice(col_a, Rotation::cur());
+            let b = meta.query_advice(col_b, Rotation::cur());
+            let c = meta.query_advice(col_c, Rotation::cur());
+            let t = meta.query_advice(col_active, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            // return the constraints
+            vec![s * (t.clone() * (a + b.clone() - c.clone()) + (one - t) * (c - b))]
+        });
+
+        meta.create_gate("active flag", |meta|{
+            // the flag is a bit, and once a row is padding every later row is padding too.
+            // Where the fixed column says so it has to be 1
+            let s = meta.query_selector(selector);
+            let t = meta.query_advice(col_active, Rotation::cur());
+            let prev_t = meta.query_advice(col_prev_active, Rotation::cur());
+            let p = meta.query_fixed(pinned, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                s.clone() * t.clone() * (one.clone() - t.clone()),
+                s.clone() * t.clone() * (one.clone() - prev_t),
+                s * p * (one - t),
+            ]
+        });
+
+        meta.create_gate("difference", |meta|{
+            // f(n) - f(n-1) = f(n-2), looking one row back
+            // col_u  | col_v  | selector
+            // f(n-2) |        |
+            // f(n-1) | f(n)   |  diff
+            let s = meta.query_selector(diff);
+            let u_prev = meta.query_advice(col_u, Rotation::prev());
+            let u = meta.query_advice(col_u, Rotation::cur());
+            let v = meta.query_advice(col_v, Rotation::cur());
+            vec![s * (v - u - u_prev)]
+        });
+
+        FiboConfig {
+            advice: [col_a, col_b, col_c],
+            active: [col_active, col_prev_active],
+            pinned,
+            select: selector,
+            diff_advice: [col_u, col_v],
+            diff,
+            // shares col_a and col_b, the decompositions get regions of their own
+            parity_bits: BitsChip::configure_with_columns(meta, pool),
+            reserved: vec![],
+            instance
+        }
+    }
+
+    pub fn assign_first_row(&self, mut layouter: impl Layouter<F>, a: Option<F>, b: Option<F>) -> Result<FirstRow<F>, Error>{
+        layouter.assign_region(||"first row",
+    |mut region|{
+
+            self.config.select.enable(&mut region, 0)?; // kind of region selector
+
+            // can also use assign_advice_from_instance
+            let a_cell = region.assign_advice(
+                || "a",
+                 self.config.advice[0], // column selector
+                  0,
+                ||a.ok_or(Error::Synthesis)).map(ACell)?;
+
+            let b_cell = region.assign_advice(
+                || "b",
+                    self.config.advice[1],
+                    0,
+                ||b.ok_or(Error::Synthesis)).map(ACell)?;
+
+            let c_val = a.and_then(|a| b.map(|b| a+b));
+
+            let c_cell = region.assign_advice(||"c",
+            self.config.advice[2], 0, ||c_val.ok_or(Error::Synthesis)).map(ACell)?;
+
+            // the first row always adds
+            region.assign_fixed(|| "pinned", self.config.pinned, 0, || Ok(F::one()))?;
+            let active_cell = region.assign_advice_from_constant(
+                || "active", self.config.active[0], 0, F::one()).map(ACell)?;
+            region.assign_advice_from_constant(
+                || "prev active", self.config.active[1], 0, F::one())?;
+
+            Ok((a_cell, b_cell, c_cell, active_cell))
+        })
+    }
+
+    // Only return the last cell, and this rows active flag. The flag is a witness, which
+    // padded traces need since their vk can't depend on where the padding starts
+    pub fn assign_row(&self, layouter: impl Layouter<F>, prev_b: &ACell<F>, prev_c: &ACell<F>,
+        prev_active: &ACell<F>, active: bool) -> Result<(ACell<F>, ACell<F>), Error>{
+        self.assign_row_with(layouter, prev_b, prev_c, prev_active, active, false)
+    }
+
+    // assign_row for a row that always adds, with the active flag pinned to 1 by the fixed
+    // column. An unpadded trace uses this for every row, so a prover can't stop the sequence
+    // early by clearing the flag and carrying a smaller output to the end
+    pub fn assign_pinned_row(&self, layouter: impl Layouter<F>, prev_b: &ACell<F>, prev_c: &ACell<F>,
+        prev_active: &ACell<F>) -> Result<(ACell<F>, ACell<F>), Error>{
+        self.assign_row_with(layouter, prev_b, prev_c, prev_active, true, true)
+    }
+
+    fn assign_row_with(&self, mut layouter: impl Layouter<F>, prev_b: &ACell<F>, prev_c: &ACell<F>,
+        prev_active: &ACell<F>, active: bool, pinned: bool) -> Result<(ACell<F>, ACell<F>), Error>{
+        layouter.assign_region(||"next row", |mut region| {
+            self.config.select.enable(&mut region, 0)?;
+
+            // copy the value from prev_b to this rows b and constrains them to be equal
+            // ** -- PERMUTATION CHECK -- **
+            prev_b.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+            prev_c.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+            prev_active.0.copy_advice(|| "prev active", &mut region, self.config.active[1], 0)?;
+
+            let c_val = if active {
+                cell_value(prev_b).and_then(|b| cell_value(prev_c).map(|c| b + c))
+            } else {
+                // padding, carry the last value forward
+                cell_value(prev_c)
+            };
+
+            let c_cell = region.assign_advice(|| "c",
+            self.config.advice[2], 0, ||c_val.ok_or(Error::Synthesis)).map(ACell)?;
+
+            let pinned_val = if pinned { F::one() } else { F::zero() };
+            region.assign_fixed(|| "pinned", self.config.pinned, 0, || Ok(pinned_val))?;
+            let active_val = if active { F::one() } else { F::zero() };
+            let active_cell = region.assign_advice(|| "active",
+            self.config.active[0], 0, || Ok(active_val)).map(ACell)?;
+
+            Ok((c_cell, active_cell))
+        })
+    }
+
+    // Redundant check over the terms f(0), f(1), .. in a region of their own, on copies
+    // of the trace cells. Catches a witness that gets past the add gate when building
+    // circuits by hand. Runs next to the trace, so it costs columns but no rows
+    pub fn difference_check(&self, mut layouter: impl Layouter<F>, terms: &[ACell<F>]) -> Result<(), Error> {
+        layouter.assign_region(||"difference", |mut region| {
+            for (row, pair) in terms.windows(2).enumerate() {
+                if row > 0 {
+                    self.config.diff.enable(&mut region, row)?;
+                }
+                pair[0].0.copy_advice(|| "u", &mut region, self.config.diff_advice[0], row)?;
+                pair[1].0.copy_advice(|| "v", &mut region, self.config.diff_advice[1], row)?;
+            }
+            Ok(())
+        })
+    }
+
+    // Parities of f(0), f(1), .. repeat odd, odd, even, which is what you get from two odd
+    // seeds. Each term is decomposed into bits and its lowest bit pinned to the pattern, so
+    // unlike the other checks this one costs PARITY_BITS + 1 rows per term
+    pub fn parity_check(&self, mut layouter: impl Layouter<F>, terms: &[ACell<F>]) -> Result<(), Error> {
+        let bits = BitsChip::construct(self.config.parity_bits.clone());
+        let lowest_bits = terms.iter()
+            .map(|term| bits.decompose(layouter.namespace(|| "term bits"), term, PARITY_BITS).map(|b| b[0].clone()))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        // the expected parities as constants, one row per term
+        layouter.assign_region(|| "parity", |mut region| {
+            for (i, bit) in lowest_bits.iter().enumerate() {
+                let odd = if i % 3 == 2 { F::zero() } else { F::one() };
+                let expected = region.assign_advice_from_constant(|| "parity",
+                    self.config.parity_bits.bit, i, odd)?;
+                region.constrain_equal(expected.cell(), bit.0.cell())?;
+            }
+            Ok(())
+        })
+    }
+
+    // f(0)..f(steps) for seeds pinned to the constant 1, for chips that build on the standard
+    // sequence instead of taking seeds
+    pub fn unit_trace(&self, mut layouter: impl Layouter<F>, steps: usize) -> Result<Vec<ACell<F>>, Error> {
+        let trace = FibonacciCircuit::new(Some(F::one()), Some(F::one()), steps).with_private_seeds();
+        let cells = trace.compute_all(self, layouter.namespace(|| "trace"))?;
+
+        layouter.assign_region(|| "unit seeds", |mut region| {
+            for (row, seed) in cells[..2].iter().enumerate() {
+                let one = region.assign_advice_from_constant(|| "one", self.config.advice[0], row, F::one())?;
+                region.constrain_equal(one.cell(), seed.0.cell())?;
+            }
+            Ok(())
+        })?;
+        Ok(cells)
+    }
+
+    pub fn expose_public(&self,
+        mut layouter: impl Layouter<F>,
+        cell: &ACell<F>,
+        row: usize
+    ) -> Result<(), Error>{
+        layouter.constrain_instance(cell.0.cell(), self.config.instance, row)
+    }
+
+    // Exposes only the lowest bit of the output, 1 for odd, out of the same decomposition
+    // parity_check uses, so the output has to be smaller than 2^PARITY_BITS
+    pub fn expose_parity(&self, mut layouter: impl Layouter<F>, output: &ACell<F>, row: usize) -> Result<(), Error> {
+        let bits = BitsChip::construct(self.config.parity_bits.clone());
+        let bits = bits.decompose(layouter.namespace(|| "output bits"), output, PARITY_BITS)?;
+        self.expose_public(layouter, &bits[0], row)
+    }
+}
+
+// Proves f(steps) = out for public seeds f(0) = a, f(1) = b.
+// The first row always computes f(2), for steps 0 and 1 the output is just the seed
+// and nothing past the first row is assigned (unless padded)
+#[derive(Default, Clone)]
+pub struct FibonacciCircuit<F> {
+    pub a: Option<F>,
+    pub b: Option<F>,
+    pub steps: usize,
+    // when set, the circuit fills every usable row of 2^k, see `pad_to_k`
+    pub pad_k: Option<u32>,
+    // runs difference_check over the active terms as well
+    pub difference_check: bool,
+    // runs parity_check over the active terms, needs both seeds odd
+    pub parity_check: bool,
+    // the seeds stay witness only and the output is the only public input, see `prove_preimage`
+    pub private_seeds: bool,
+    // the step whose term is exposed, f(steps) when None, see `output_at_step`
+    pub output_step: Option<usize>,
+    // the public output is the parity of the output term instead, see `expose_parity`
+    pub public_parity: bool,
+}
+
+impl<F: FieldExt> FibonacciCircuit<F> {
+    pub fn new(a: Option<F>, b: Option<F>, steps: usize) -> Self {
+        Self { a, b, steps, pad_k: None, difference_check: false, parity_check: false, private_seeds: false, output_step: None, public_parity: false }
+    }
+
+    // Seeds derived from a label, see `seeds_from_label`
+    pub fn from_label(label: &[u8], steps: usize) -> Self {
+        let (a, b) = seeds_from_label(label);
+        Self::new(Some(a), Some(b), steps)
+    }
+
+    // Pads the circuit with no-op rows up to the usable rows of 2^k, so every step count
+    // that fits shares the same layout and therefore the same keys.
+    // A padding row has its active flag cleared and carries the last value forward, and the
+    // flag can never be set again after it, so the output is still f(m) for the m active rows.
+    // Note the step count itself is not public, only the seeds and the output are.
+    pub fn pad_to_k(self, k: u32) -> Self {
+        Self { pad_k: Some(k), ..self }
+    }
+
+    pub fn with_difference_check(self) -> Self {
+        Self { difference_check: true, ..self }
+    }
+
+    // Not together with pad_to_k, the padding already takes every usable row (synthesis
+    // fails with PaddedCheck)
+    pub fn with_parity_check(self) -> Self {
+        Self { parity_check: true, ..self }
+    }
+
+    pub fn with_private_seeds(self) -> Self {
+        Self { private_seeds: true, ..self }
+    }
+
+    // Exposes f(n) instead of f(steps), the trace still runs to f(steps).
+    // n is part of the copy constraints, so it's part of the keys too. OutputPastTrace for
+    // an n past steps
+    pub fn output_at_step(self, n: usize) -> Result<Self, FiboError> {
+        if n > self.steps {
+            return Err(FiboError::OutputPastTrace { step: n, steps: self.steps });
+        }
+        Ok(Self { output_step: Some(n), ..self })
+    }
+
+    // Only the parity of the output is public, to keep the value itself hidden the seeds have
+    // to be private too. Not together with pad_to_k, like the parity check synthesis then
+    // fails with PaddedCheck
+    pub fn with_public_parity(self) -> Self {
+        Self { public_parity: true, ..self }
+    }
+
+    // The seeds a and b (unless private), then the output
+    pub fn instance_layout(&self) -> InstanceLayout {
+        let layout = InstanceLayout::new();
+        let layout = if self.private_seeds { layout } else { layout.slot("a").slot("b") };
+        layout.slot("output")
+    }
+
+    // Instance row of the output, after the seeds unless they are private
+    pub fn output_row(&self) -> usize {
+        self.instance_layout().position("output").1
+    }
+
+    // Smallest k the circuit fits in, the padded size if padded
+    pub fn min_k(&self) -> u32 {
+        let rows = self.rows_needed();
+        self.pad_k.unwrap_or_else(|| (1..).find(|&k| usable_rows::<F>(k) >= rows).unwrap())
+    }
+
+    // Rows the unpadded circuit assigns to
+    fn rows_needed(&self) -> usize {
+        let mut rows = self.steps.saturating_sub(1).max(3); // the instance column needs 3 rows too
+        if self.parity_check {
+            // one decomposition and one parity row per term f(0)..f(steps), at least f(0)..f(2)
+            rows += (self.steps.max(2) + 1) * (PARITY_BITS + 1);
+        }
+        if self.public_parity {
+            rows += PARITY_BITS + 1;
+        }
+        rows
+    }
+
+    // Assigns the whole trace, exposes the seeds (if public), and returns every term in order,
+    // so f(0)..f(steps) (followed by the carried values of the padding rows, if padded).
+    // Always at least f(0), f(1), f(2) since the first row is always there.
+    // synthesize can't hand cells back, so anything that needs the full trace calls this
+    pub fn compute_all(&self, chip: &FiboChip<F>, layouter: impl Layouter<F>) -> Result<Vec<ACell<F>>, Error> {
+        Ok(self.compute_all_within(chip, layouter, None)?)
+    }
+
+    // compute_all, but with `available` usable rows it stops at the first trace row past
+    // them with OutOfRows instead of leaving it to the backend. Assumes the trace starts at
+    // row 0, as it does in FibonacciCircuit itself
+    pub fn compute_all_within(&self, chip: &FiboChip<F>, mut layouter: impl Layouter<F>,
+        available: Option<usize>) -> Result<Vec<ACell<F>>, FiboError> {
+        // the trace row at offset `row` computes f(row + 2)
+        let check_row = |row: usize| match available {
+            Some(available) if row >= available => Err(FiboError::OutOfRows { step: row + 2, available }),
+            _ => Ok(()),
+        };
+
+        check_row(0)?;
+        let (prev_a, mut prev_b, mut prev_c, mut prev_active) = chip.assign_first_row(
+            layouter.namespace(|| "first row"), self.a, self.b)?;
+
+        if !self.private_seeds {
+            let (layout, instance) = (self.instance_layout(), [chip.config.instance]);
+            layout.expose(layouter.namespace(||"priv a"), &instance, "a", &prev_a)?;
+            layout.expose(layouter.namespace(||"priv b"), &instance, "b", &prev_b)?;
+        }
+
+        // the first row is f(2), every further row is one more term
+        let rows = match self.pad_k {
+            Some(k) => usable_rows::<F>(k),
+            None => self.steps.saturating_sub(1),
+        };
+
+        let mut terms = vec![prev_a.clone(), prev_b.clone(), prev_c.clone()];
+        let mut cells = terms.clone();
+        // we have to prove f(steps) = z
+        for row in 1..rows {
+            check_row(row)?;
+            let active = row + 2 <= self.steps;
+            // assign row
+            let (c_cell, active_cell) = match self.pad_k {
+                Some(_) => chip.assign_row(layouter.namespace(||"next row"), &prev_b, &prev_c, &prev_active, active)?,
+                None => chip.assign_pinned_row(layouter.namespace(||"next row"), &prev_b, &prev_c, &prev_active)?,
+            };
+            if active {
+                terms.push(c_cell.clone());
+            }
+
+            prev_b = prev_c;
+            prev_c = c_cell;
+            prev_active = active_cell;
+            cells.push(prev_c.clone());
+        }
+
+        if self.difference_check {
+            chip.difference_check(layouter.namespace(|| "difference"), &terms)?;
+        }
+        if self.parity_check {
+            if self.pad_k.is_some() {
+                return Err(FiboError::PaddedCheck("parity check"));
+            }
+            chip.parity_check(layouter.namespace(|| "parity"), &terms)?;
+        }
+
+        Ok(cells)
+    }
+}
+
+// Deterministic seeds for a label, e.g. one sequence per user. Each seed is a 64 byte
+// Blake2b digest of the label (with the seed index appended) reduced mod the field
+// modulus, wide enough that the reduction is close to uniform
+pub fn seeds_from_label<F: FieldExt>(label: &[u8]) -> (F, F) {
+    let seed = |index: u8| {
+        let digest = blake2b_simd::Params::new()
+            .hash_length(64)
+            .personal(b"halo2-circ-seed_")
+            .to_state()
+            .update(label)
+            .update(&[index])
+            .finalize();
+        let mut wide = [0u8; 64];
+        wide.copy_from_slice(digest.as_bytes());
+        F::from_bytes_wide(&wide)
+    };
+    (seed(0), seed(1))
+}
+
+// f(n) for seeds f(0) = a, f(1) = b, outside the circuit
+pub fn fib_native<F: FieldExt>(a: F, b: F, n: usize) -> F {
+    let (mut a, mut b) = (a, b);
+    for _ in 0..n {
+        let c = a + b;
+        a = b;
+        b = c;
+    }
+    a
+}
+
+// f(0)..f(steps) recomputed from the public [a, b, out] of a circuit with public seeds, which
+// fix every term of the trace, so the intermediate values can be audited without the
+// witness. Fails unless there are exactly three public inputs and the output is f(steps)
+// of those seeds
+pub fn reconstruct_trace<F: FieldExt>(public: &[F], steps: usize) -> Result<Vec<F>, FiboError> {
+    let [a, b, out] = <[F; 3]>::try_from(public)
+        .map_err(|_| FiboError::PublicLength { expected: 3, found: public.len() })?;
+    let trace: Vec<F> = (0..=steps).map(|n| fib_native(a, b, n)).collect();
+    if trace[steps] != out {
+        return Err(FiboError::OutputMismatch { steps });
+    }
+    Ok(trace)
+}
+
+// Rows of a 2^k circuit we can assign to, halo2 reserves the rest for blinding
+pub fn usable_rows<F: FieldExt>(k: u32) -> usize {
+    usable_rows_of::<F, FibonacciCircuit<F>>(k)
+}
+
+fn usable_rows_of<F: FieldExt, C: Circuit<F>>(k: u32) -> usize {
+    let mut meta = ConstraintSystem::<F>::default();
+    C::configure(&mut meta);
+    (1usize << k).saturating_sub(meta.blinding_factors() + 1)
+}
+
+impl<F: FieldExt> Circuit<F> for FibonacciCircuit<F> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    // Circuit without witnesses, called only during key generation
+    // Keeps the shape (steps, padding) so the keys match the real circuit
+    fn without_witnesses(&self) -> Self {
+        Self { a: None, b: None, ..self.clone() }
+    }
+
+    // Has the arrangement of columns. Called only during keygen, and will just call chip config most of the time
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config { //just tells about local gate constraints
+        let instance = meta.instance_column();
+        FiboChip::configure(meta, instance)
+    }
+
+
+    // Take the output of configure and floorplanner type to make the actual circuit
+    // Called both at key generation time, and proving time with a specific witness
+    // *** Will call all of the copy constraints ***
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        #[cfg(feature = "profile")]
+        let layouter = crate::profile::ProfiledLayouter::new(layouter);
+        Ok(self.synthesize_within(config, layouter, None)?)
+    }
+}
+
+impl<F: FieldExt> FibonacciCircuit<F> {
+    fn synthesize_within(&self, config: FiboConfig, mut layouter: impl Layouter<F>,
+        available: Option<usize>) -> Result<(), FiboError> {
+        let chip = FiboChip::construct(config);
+
+        let cells = self.compute_all_within(&chip, layouter.namespace(|| "trace"), available)?;
+
+        // for steps < 2 the output is a seed. Padded this is still a different copy
+        // constraint than for steps >= 2, so those don't share keys with the rest
+        let output = match self.output_step {
+            Some(n) => &cells[n],
+            None if self.steps < 2 => &cells[self.steps],
+            None => cells.last().unwrap(),
+        };
+        if self.public_parity {
+            if self.pad_k.is_some() {
+                return Err(FiboError::PaddedCheck("parity bits"));
+            }
+            chip.expose_parity(layouter.namespace(|| "output parity"), output, self.output_row())?;
+        } else {
+            self.instance_layout().expose(layouter.namespace(||"output"), &[chip.config.instance], "output", output)?;
+        }
+
+        Ok(())
+    }
+
+    // MockProver run at k. A trace longer than the usable rows of 2^k fails with
+    // OutOfRows at the step that no longer fits, where MockProver alone only reports that
+    // synthesis failed
+    pub fn mock_run(&self, k: u32, public: Vec<Vec<F>>) -> Result<MockProver<F>, FiboError> {
+        let checked = RowChecked { circuit: self, available: usable_rows::<F>(k), error: RefCell::new(None) };
+        MockProver::run(k, &checked, public).map_err(|e| checked.error.take().unwrap_or(FiboError::Halo2(e)))
+    }
+}
+
+// FibonacciCircuit with its trace checked against the usable rows, holding on to the
+// FiboError that synthesize itself has to turn into a plain Error
+struct RowChecked<'c, F> {
+    circuit: &'c FibonacciCircuit<F>,
+    available: usize,
+    error: RefCell<Option<FiboError>>,
+}
+
+impl<F: FieldExt> Circuit<F> for RowChecked<'_, F> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { circuit: self.circuit, available: self.available, error: RefCell::new(None) }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciCircuit::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.circuit.synthesize_within(config, layouter, Some(self.available)).map_err(|e| match e {
+            FiboError::Halo2(e) => e,
+            e => {
+                self.error.borrow_mut().get_or_insert(e);
+                Error::Synthesis
+            }
+        })
+    }
+}
+
+// The seeds (unless private) and f(steps) (or the output_at_step term, or its parity), needs
+// the seeds to be known
+impl<F: FieldExt> SelfVerifying<F> for FibonacciCircuit<F> {
+    fn expected_public(&self) -> Vec<Vec<F>> {
+        let (a, b) = (self.a.expect("seed a"), self.b.expect("seed b"));
+        let out = fib_native(a, b, self.output_step.unwrap_or(self.steps));
+        let out = if self.public_parity { F::from(out.get_lower_128() as u64 & 1) } else { out };
+        let layout = self.instance_layout();
+        if self.private_seeds {
+            layout.public(&[("output", out)])
+        } else {
+            layout.public(&[("a", a), ("b", b), ("output", out)])
+        }
+    }
+}
+
+// FibonacciCircuit configured with a minimum degree, which has to be known in the static
+// configure, hence the const parameter
+#[derive(Default, Clone)]
+pub struct ZkFibonacciCircuit<F, const MIN_DEGREE: usize>(pub FibonacciCircuit<F>);
+
+impl<F: FieldExt, const MIN_DEGREE: usize> Circuit<F> for ZkFibonacciCircuit<F, MIN_DEGREE> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        FiboChip::configure_zk(meta, instance, &ZkConfig { min_degree: Some(MIN_DEGREE) })
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+// FibonacciCircuit with RESERVED extra advice columns and BLINDING extra blinding rows,
+// see RecursionConfig. Const parameters for the same reason as ZkFibonacciCircuit.
+// The trace is laid out exactly as in FibonacciCircuit, so the same public inputs verify
+#[derive(Default, Clone)]
+pub struct RecursionFibonacciCircuit<F, const RESERVED: usize, const BLINDING: usize>(pub FibonacciCircuit<F>);
+
+impl<F: FieldExt, const RESERVED: usize, const BLINDING: usize> RecursionFibonacciCircuit<F, RESERVED, BLINDING> {
+    pub fn recursion_config() -> RecursionConfig {
+        RecursionConfig { reserved_advice: RESERVED, extra_blinding: BLINDING }
+    }
+
+    // Smallest k with room for the trace after the larger blinding margin. Padding sizes
+    // the trace for the standard margin, so it doesn't combine with this one
+    pub fn min_k(&self) -> u32 {
+        assert!(self.0.pad_k.is_none(), "pad_to_k assumes the standard blinding margin");
+        let rows = self.0.rows_needed();
+        (1..).find(|&k| usable_rows_of::<F, Self>(k) >= rows).unwrap()
+    }
+}
+
+impl<F: FieldExt, const RESERVED: usize, const BLINDING: usize> Circuit<F> for RecursionFibonacciCircuit<F, RESERVED, BLINDING> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self(self.0.without_witnesses())
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        FiboChip::configure_recursion(meta, instance, &Self::recursion_config())
+    }
+
+    fn synthesize(&self, config: Self::Config, layouter: impl Layouter<F>) -> Result<(), Error> {
+        self.0.synthesize(config, layouter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        dev::VerifyFailure,
+        pasta::{EqAffine, Fp},
+        plonk::keygen_vk,
+        poly::commitment::Params,
+    };
+
+    use super::*;
+    use crate::prover::{keygen, prove, verify, vk_bytes, ProofRng};
+
+    // f(steps) padded to k = 6, with a padding row written by hand at offset `at`: its c
+    // and active flag are whatever the test puts there
+    #[derive(Clone)]
+    struct ForgedPadding {
+        steps: usize,
+        at: usize,
+        c: Fp,
+        active: Fp,
+    }
+
+    impl Circuit<Fp> for ForgedPadding {
+        type Config = FiboConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = FiboChip::construct(config.clone());
+            let (a, mut b, mut c, mut active) = chip.assign_first_row(layouter.namespace(|| "first row"), Some(Fp::one()), Some(Fp::one()))?;
+            chip.expose_public(layouter.namespace(|| "a"), &a, 0)?;
+            chip.expose_public(layouter.namespace(|| "b"), &b, 1)?;
+
+            for row in 1..usable_rows::<Fp>(6) {
+                let (next_c, next_active) = if row == self.at {
+                    layouter.assign_region(|| "forged row", |mut region| {
+                        config.select.enable(&mut region, 0)?;
+                        b.0.copy_advice(|| "a", &mut region, config.advice[0], 0)?;
+                        c.0.copy_advice(|| "b", &mut region, config.advice[1], 0)?;
+                        active.0.copy_advice(|| "prev active", &mut region, config.active[1], 0)?;
+                        region.assign_fixed(|| "pinned", config.pinned, 0, || Ok(Fp::zero()))?;
+                        let c = region.assign_advice(|| "c", config.advice[2], 0, || Ok(self.c)).map(ACell)?;
+                        let active = region.assign_advice(|| "active", config.active[0], 0, || Ok(self.active)).map(ACell)?;
+                        Ok((c, active))
+                    })?
+                } else {
+                    chip.assign_row(layouter.namespace(|| "next row"), &b, &c, &active, row + 2 <= self.steps)?
+                };
+                b = c;
+                c = next_c;
+                active = next_active;
+            }
+            chip.expose_public(layouter.namespace(|| "out"), &c, 2)
+        }
+    }
+
+    // The rows of unit(9), but with the active flag witnessed and cleared from offset
+    // `stop` on, so the sequence stops and carries f(stop + 1) to the end
+    #[derive(Clone)]
+    struct ForgedEarlyStop {
+        stop: usize,
+    }
+
+    impl Circuit<Fp> for ForgedEarlyStop {
+        type Config = FiboConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = FiboChip::construct(config);
+            let (a, mut b, mut c, mut active) = chip.assign_first_row(layouter.namespace(|| "first row"), Some(Fp::one()), Some(Fp::one()))?;
+            chip.expose_public(layouter.namespace(|| "a"), &a, 0)?;
+            chip.expose_public(layouter.namespace(|| "b"), &b, 1)?;
+            for row in 1..8 {
+                let (next_c, next_active) = chip.assign_row(layouter.namespace(|| "next row"), &b, &c, &active, row < self.stop)?;
+                b = c;
+                c = next_c;
+                active = next_active;
+            }
+            chip.expose_public(layouter.namespace(|| "out"), &c, 2)
+        }
+    }
+
+    // Runs compute_all and keeps the cell_value of every cell it returns
+    struct Capture {
+        circuit: FibonacciCircuit<Fp>,
+        values: RefCell<Vec<Option<Fp>>>,
+    }
+
+    impl Capture {
+        fn new(circuit: FibonacciCircuit<Fp>) -> Self {
+            Self { circuit, values: RefCell::new(vec![]) }
+        }
+    }
+
+    impl Circuit<Fp> for Capture {
+        type Config = FiboConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::new(self.circuit.without_witnesses())
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = FiboChip::construct(config);
+            let cells = self.circuit.compute_all(&chip, layouter)?;
+            *self.values.borrow_mut() = cells.iter().map(cell_value).collect();
+            Ok(())
+        }
+    }
+
+    fn unit(steps: usize) -> FibonacciCircuit<Fp> {
+        FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), steps)
+    }
+
+    #[test]
+    fn cell_value_reads_the_output() {
+        let capture = Capture::new(unit(9));
+        MockProver::run(4, &capture, unit(9).expected_public()).unwrap();
+        assert_eq!(capture.values.borrow().last().copied().flatten(), Some(Fp::from(55)));
+    }
+
+    #[test]
+    fn cell_value_is_none_without_a_witness() {
+        // keygen assigns the cells without looking at their values
+        let capture = Capture::new(unit(9).without_witnesses());
+        keygen_vk(&Params::<EqAffine>::new(4), &capture).unwrap();
+        let values = capture.values.borrow();
+        assert_eq!(values.len(), 10);
+        assert!(values.iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn compute_all_returns_every_term() {
+        let capture = Capture::new(unit(9));
+        MockProver::run(4, &capture, unit(9).expected_public()).unwrap();
+        let values: Vec<_> = capture.values.borrow().iter().map(|v| v.unwrap()).collect();
+        // the seeds, then the c cell of every row
+        assert_eq!(values[..2], [Fp::one(), Fp::one()]);
+        assert_eq!(values[2..], [2, 3, 5, 8, 13, 21, 34, 55].map(Fp::from));
+    }
+
+    #[test]
+    fn reconstructed_trace_matches_the_assignments() {
+        let public = [1, 1, 55].map(Fp::from);
+        let trace = reconstruct_trace(&public, 9).unwrap();
+        let capture = Capture::new(unit(9));
+        MockProver::run(4, &capture, vec![public.to_vec()]).unwrap().assert_satisfied();
+        let assigned: Vec<_> = capture.values.borrow().iter().map(|v| v.unwrap()).collect();
+        assert_eq!(trace, assigned);
+    }
+
+    #[test]
+    fn reconstruct_trace_rejects_bad_public_inputs() {
+        let error = reconstruct_trace(&[1, 1, 56].map(Fp::from), 9).err();
+        assert!(matches!(error, Some(FiboError::OutputMismatch { steps: 9 })), "{:?}", error);
+        let error = reconstruct_trace(&[Fp::one(), Fp::from(55)], 9).err();
+        assert!(matches!(error, Some(FiboError::PublicLength { expected: 3, found: 2 })), "{:?}", error);
+    }
+
+    #[test]
+    fn recursion_margin_proves_standard_instances() {
+        type Recursion = RecursionFibonacciCircuit<Fp, 2, 3>;
+        let circuit: Recursion = RecursionFibonacciCircuit(unit(9));
+        let public = unit(9).expected_public().remove(0);
+        let k = circuit.min_k();
+        let params = Params::<EqAffine>::new(k);
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit, &public, ProofRng::seeded(0)).unwrap();
+        verify(&params, pk.get_vk(), &public, &proof).unwrap();
+
+        // two reserved columns, and three rows less to assign to
+        let plain = keygen_vk(&Params::<EqAffine>::new(k), &unit(9)).unwrap();
+        assert_eq!(crate::inspect::advice_columns(pk.get_vk()), crate::inspect::advice_columns(&plain) + 2);
+        assert_eq!(usable_rows_of::<Fp, Recursion>(k) + 3, usable_rows::<Fp>(k));
+    }
+
+    #[test]
+    fn min_degree_grows_the_extended_domain() {
+        let params = Params::<EqAffine>::new(4);
+        let extended = |vk: &VerifyingKey<EqAffine>| vk.get_domain().empty_extended().len();
+        let plain = keygen_vk(&params, &unit(9)).unwrap();
+        let raised = keygen_vk(&params, &ZkFibonacciCircuit::<Fp, 9>(unit(9))).unwrap();
+        assert!(extended(&raised) > extended(&plain));
+
+        // the degree doesn't take rows in halo2 0.1, so the same k still fits, and proves
+        let mut meta = ConstraintSystem::<Fp>::default();
+        ZkFibonacciCircuit::<Fp, 9>::configure(&mut meta);
+        assert_eq!(meta.degree(), 9);
+        assert_eq!(usable_rows_of::<Fp, ZkFibonacciCircuit<Fp, 9>>(4), usable_rows::<Fp>(4));
+        assert!(crate::dev::full_roundtrip(&ZkFibonacciCircuit::<Fp, 9>(unit(9)), 4, unit(9).expected_public().remove(0)));
+    }
+
+    // difference_check over the f(9) trace with term `forged` swapped for a free witness one
+    // larger, the trace itself (and so the add gate) untouched
+    struct ForgedDifference {
+        forged: Option<usize>,
+    }
+
+    impl Circuit<Fp> for ForgedDifference {
+        type Config = FiboConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self { forged: self.forged }
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = FiboChip::construct(config.clone());
+            let mut terms = unit(9).compute_all(&chip, layouter.namespace(|| "trace"))?;
+            if let Some(n) = self.forged {
+                let value = cell_value(&terms[n]).map(|v| v + Fp::one());
+                terms[n] = crate::layouter_ext::LayouterExt::assign_value(&mut layouter, "forged", config.diff_advice[0], value)?;
+            }
+            chip.difference_check(layouter.namespace(|| "difference"), &terms)
+        }
+    }
+
+    #[test]
+    fn difference_check_holds_on_a_real_trace() {
+        let circuit = unit(9).with_difference_check();
+        assert!(crate::dev::full_roundtrip(&circuit, 4, circuit.expected_public().remove(0)));
+        crate::assert_circuit!(ForgedDifference { forged: None }, 4, vec![vec![Fp::one(), Fp::one()]]);
+    }
+
+    #[test]
+    fn difference_check_rejects_a_forged_term() {
+        crate::assert_circuit_fails!(ForgedDifference { forged: Some(5) }, 4, vec![vec![Fp::one(), Fp::one()]],
+            VerifyFailure::ConstraintNotSatisfied { .. });
+    }
+
+    #[test]
+    fn zero_and_one_step_output_a_seed() {
+        // f(0) = a and f(1) = b, from the first row alone like f(2)
+        let seeds = |steps| FibonacciCircuit::new(Some(Fp::from(2)), Some(Fp::from(3)), steps);
+        let regions = |steps| crate::inspect::region_starts(&seeds(steps)).len();
+        for (steps, seed) in [(0, 2), (1, 3)] {
+            let circuit = seeds(steps);
+            assert_eq!(circuit.expected_public(), vec![vec![Fp::from(2), Fp::from(3), Fp::from(seed)]]);
+            assert_eq!(regions(steps), regions(2));
+            assert!(crate::dev::full_roundtrip(&circuit, 4, circuit.expected_public().remove(0)));
+        }
+        // f(3) is the first step with a next row region
+        assert_eq!(regions(3), regions(2) + 1);
+    }
+
+    #[test]
+    fn seeds_from_label_are_deterministic() {
+        let seeds = seeds_from_label::<Fp>(b"alice");
+        assert_eq!(seeds, seeds_from_label(b"alice"));
+        assert_ne!(seeds.0, seeds.1);
+        assert_ne!(seeds, seeds_from_label(b"bob"));
+        assert_ne!(seeds, seeds_from_label(b"alice "));
+
+        let circuit = FibonacciCircuit::<Fp>::from_label(b"alice", 9);
+        assert_eq!((circuit.a, circuit.b), (Some(seeds.0), Some(seeds.1)));
+        circuit.mock_prove(4);
+    }
+
+    // parity_check over f(0..=12) with term 4 swapped for an even free witness
+    struct ForgedParity;
+
+    impl Circuit<Fp> for ForgedParity {
+        type Config = FiboConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let chip = FiboChip::construct(config.clone());
+            let mut terms = unit(12).compute_all(&chip, layouter.namespace(|| "trace"))?;
+            // f(4) = 5
+            terms[4] = crate::layouter_ext::LayouterExt::assign_value(&mut layouter, "forged", config.diff_advice[0], Some(Fp::from(6)))?;
+            chip.parity_check(layouter.namespace(|| "parity"), &terms)
+        }
+    }
+
+    #[test]
+    fn parity_pattern_holds_up_to_f12() {
+        let circuit = unit(12).with_parity_check();
+        circuit.mock_prove(circuit.min_k());
+    }
+
+    #[test]
+    fn parity_check_rejects_an_even_term() {
+        let k = unit(12).with_parity_check().min_k();
+        crate::assert_circuit_fails!(ForgedParity, k, vec![vec![Fp::one(), Fp::one()]], VerifyFailure::Permutation { .. });
+    }
+
+    #[test]
+    fn parity_check_needs_unpadded_rows() {
+        let circuit = unit(9).with_parity_check().pad_to_k(6);
+        let error = circuit.mock_run(6, circuit.expected_public()).err();
+        assert!(matches!(error, Some(FiboError::PaddedCheck("parity check"))), "{:?}", error);
+    }
+
+    #[test]
+    fn f9_is_public_as_odd() {
+        let circuit = unit(9).with_private_seeds().with_public_parity();
+        // 55 is odd
+        assert_eq!(circuit.expected_public(), vec![vec![Fp::one()]]);
+        let k = circuit.min_k();
+        assert!(crate::dev::full_roundtrip(&circuit, k, vec![Fp::one()]));
+        crate::assert_circuit_fails!(circuit, k, vec![vec![Fp::zero()]], VerifyFailure::Permutation { .. });
+    }
+
+    #[test]
+    fn public_parity_needs_unpadded_rows() {
+        let circuit = unit(9).with_public_parity().pad_to_k(8);
+        let error = circuit.mock_run(8, circuit.expected_public()).err();
+        assert!(matches!(error, Some(FiboError::PaddedCheck("parity bits"))), "{:?}", error);
+    }
+
+    #[test]
+    fn exposes_an_earlier_step() {
+        let circuit = unit(20).output_at_step(10).unwrap();
+        let output = *circuit.expected_public()[0].last().unwrap();
+        assert_eq!(output, fib_native(Fp::one(), Fp::one(), 10));
+        assert_ne!(output, fib_native(Fp::one(), Fp::one(), 20));
+        assert!(crate::dev::full_roundtrip(&circuit, 5, circuit.expected_public().remove(0)));
+        crate::assert_circuit_fails!(circuit, 5, unit(20).expected_public(), VerifyFailure::Permutation { .. });
+    }
+
+    #[test]
+    fn output_step_has_to_be_in_the_trace() {
+        assert!(unit(20).output_at_step(20).is_ok());
+        assert!(matches!(unit(20).output_at_step(21), Err(FiboError::OutputPastTrace { step: 21, steps: 20 })));
+    }
+
+    #[test]
+    fn reports_the_step_that_runs_out_of_rows() {
+        // k = 4 has 10 usable rows, the last of them computes f(11)
+        let error = unit(20).mock_run(4, unit(20).expected_public()).err();
+        assert!(matches!(error, Some(FiboError::OutOfRows { step: 12, available: 10 })), "{:?}", error);
+        unit(11).mock_run(4, unit(11).expected_public()).unwrap().assert_satisfied();
+
+        // padding to a larger k runs out at the same row
+        let padded = unit(9).pad_to_k(6);
+        let error = padded.mock_run(4, padded.expected_public()).err();
+        assert!(matches!(error, Some(FiboError::OutOfRows { step: 12, available: 10 })), "{:?}", error);
+    }
+
+    #[test]
+    fn padded_step_counts_share_one_vk() {
+        let params = Params::<EqAffine>::new(6);
+        let circuits = [5, 9].map(|steps| FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), steps).pad_to_k(6));
+        let keys = circuits.clone().map(|circuit| keygen(&params, &circuit).unwrap());
+        assert_eq!(vk_bytes(keys[0].get_vk()), vk_bytes(keys[1].get_vk()));
+
+        // both proofs verify against the key of the f(5) circuit
+        for (circuit, out) in circuits.into_iter().zip([8, 55]) {
+            let public = circuit.expected_public().remove(0);
+            assert_eq!(public, vec![Fp::one(), Fp::one(), Fp::from(out)]);
+            let proof = prove(&params, &keys[0], circuit, &public, ProofRng::seeded(1)).unwrap();
+            verify(&params, keys[0].get_vk(), &public, &proof).unwrap();
+        }
+    }
+
+    #[test]
+    fn padding_rows_cannot_change_the_value() {
+        // f(5) = 8, the first padding row is offset 4. Carrying 9 instead of 8 breaks the
+        // add gate, even with 9 as the claimed output
+        crate::assert_circuit!(ForgedPadding { steps: 5, at: 4, c: Fp::from(8), active: Fp::zero() }, 6,
+            vec![vec![Fp::one(), Fp::one(), Fp::from(8)]]);
+        crate::assert_circuit_fails!(ForgedPadding { steps: 5, at: 4, c: Fp::from(9), active: Fp::zero() }, 6,
+            vec![vec![Fp::one(), Fp::one(), Fp::from(9)]], VerifyFailure::ConstraintNotSatisfied { .. });
+    }
+
+    #[test]
+    fn padding_rows_cannot_become_active_again() {
+        // the row after the first padding row adding again, 8 + 8 = 16. The add gate holds,
+        // only the active flag gate catches it
+        crate::assert_circuit_fails!(ForgedPadding { steps: 5, at: 5, c: Fp::from(16), active: Fp::one() }, 6,
+            vec![vec![Fp::one(), Fp::one(), Fp::from(16)]], VerifyFailure::ConstraintNotSatisfied { .. });
+    }
+
+    #[test]
+    fn unpadded_trace_cannot_stop_early() {
+        // stopping after offset 3 carries f(5) = 8 to the output of f(9). The forged rows
+        // satisfy their own layout, but not the one of unit(9), whose flags are pinned
+        let forged = ForgedEarlyStop { stop: 4 };
+        let public = vec![Fp::one(), Fp::one(), Fp::from(8)];
+        crate::assert_circuit!(forged.clone(), 4, vec![public.clone()]);
+
+        let params = Params::<EqAffine>::new(4);
+        let pk = keygen(&params, &unit(9)).unwrap();
+        assert_ne!(vk_bytes(&keygen_vk(&params, &forged).unwrap()), vk_bytes(pk.get_vk()));
+        let proof = prove(&params, &pk, forged, &public, ProofRng::seeded(2)).unwrap();
+        assert!(verify(&params, pk.get_vk(), &public, &proof).is_err());
+    }
+}
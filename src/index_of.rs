@@ -0,0 +1,102 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    dev::find_min_k,
+    fibonacci::{ACell, FiboChip, FiboConfig, FibonacciCircuit},
+    layouter_ext::LayouterExt,
+};
+
+// Inverse Fibonacci: value is f(n) of the standard sequence (f(0) = f(1) = 1) for a claimed n.
+// There's no closed form to check n = round(log_phi(f(n) * sqrt 5)) against in a circuit,
+// so f(n) is recomputed with FiboChip's unit_trace and copy constrained to value.
+// n sizes the trace, so it's part of the circuit shape rather than a witness
+pub struct IndexOfChip<F: FieldExt> {
+    config: FiboConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> IndexOfChip<F> {
+    pub fn construct(config: FiboConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> FiboConfig {
+        FibonacciCircuit::configure(meta)
+    }
+
+    pub fn index_of(&self, mut layouter: impl Layouter<F>, value: &ACell<F>, claimed_index: usize) -> Result<(), Error> {
+        let cells = FiboChip::construct(self.config.clone())
+            .unit_trace(layouter.namespace(|| "unit trace"), claimed_index)?;
+        let term = &cells[claimed_index];
+
+        layouter.assign_region(|| "index of", |mut region| {
+            let copy = term.0.copy_advice(|| "f(n)", &mut region, self.config.advice[0], 0)?;
+            region.constrain_equal(copy.cell(), value.0.cell())
+        })
+    }
+}
+
+// Public value at instance row 0, the claimed index is the circuit's
+#[derive(Default, Clone)]
+pub struct IndexOfCircuit<F> {
+    pub claimed_index: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> IndexOfCircuit<F> {
+    pub fn new(claimed_index: usize) -> Self {
+        Self { claimed_index, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for IndexOfCircuit<F> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        IndexOfChip::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let value = layouter.assign_from_instance("value", config.instance, 0, config.advice[1])?;
+        IndexOfChip::construct(config).index_of(layouter.namespace(|| "index of"), &value, self.claimed_index)
+    }
+}
+
+// Mock proves that value = f(claimed_index)
+pub fn prove_index_of(value: u64, claimed_index: usize) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = IndexOfCircuit::<Fp>::new(claimed_index);
+    let public = vec![vec![Fp::from(value)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_55_at_9() {
+        assert_eq!(prove_index_of(55, 9), Ok(()));
+    }
+
+    #[test]
+    fn rejects_55_at_10() {
+        // f(10) = 89
+        let failures = prove_index_of(55, 10).unwrap_err();
+        assert!(failures.iter().any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+}
@@ -0,0 +1,280 @@
+use std::{collections::HashMap, fmt};
+
+use halo2_proofs::{
+    arithmetic::{CurveAffine, Field, FieldExt},
+    plonk::*,
+};
+
+use crate::fibonacci::{usable_rows, FibonacciCircuit};
+
+// Fixed columns of a verifying key, after keygen has folded what selectors it could into
+// fixed columns of their own. Read from the pinned form, the constraint system is private
+pub fn fixed_columns<C: CurveAffine>(vk: &VerifyingKey<C>) -> usize {
+    pinned_count(vk, "num_fixed_columns")
+}
+
+// Advice columns of a verifying key, reserved ones included
+pub fn advice_columns<C: CurveAffine>(vk: &VerifyingKey<C>) -> usize {
+    pinned_count(vk, "num_advice_columns")
+}
+
+fn pinned_count<C: CurveAffine>(vk: &VerifyingKey<C>, field: &str) -> usize {
+    let debug = format!("{:?}", vk.pinned());
+    debug.split(&format!("{}: ", field)).nth(1)
+        .and_then(|rest| rest.split(',').next()?.trim().parse().ok())
+        .unwrap_or_else(|| panic!("pinned key without {}", field))
+}
+
+// What we can tell about a proof without verifying it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofInfo {
+    pub len: usize,
+    // Blake2b-256 of the proof bytes, hex encoded
+    pub hash: String,
+}
+
+impl fmt::Display for ProofInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "length: {} bytes", self.len)?;
+        write!(f, "hash:   {}", self.hash)
+    }
+}
+
+// No cryptography happens here, this only looks at the bytes
+pub fn inspect_proof(bytes: &[u8]) -> ProofInfo {
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .hash(bytes);
+    ProofInfo {
+        len: bytes.len(),
+        hash: hash.to_hex().to_string(),
+    }
+}
+
+// A cell by column and row
+type CellRef = (Column<Any>, usize);
+
+// Records the highest row anything was assigned to (or a selector enabled on), the first
+// row of every region, and the copy constraints with the names of the cells they join
+#[derive(Default)]
+struct RowCounter {
+    rows: usize,
+    region_starts: Vec<Option<usize>>,
+    in_region: bool,
+    region_name: String,
+    // "<region>: <annotation>" of every assigned cell
+    names: HashMap<CellRef, String>,
+    copies: Vec<(CellRef, CellRef)>,
+}
+
+impl RowCounter {
+    fn name(&mut self, column: Column<Any>, row: usize, annotation: String) {
+        let name = if self.in_region { format!("{}: {}", self.region_name, annotation) } else { annotation };
+        self.names.insert((column, row), name);
+    }
+
+    fn touch(&mut self, row: usize) {
+        self.rows = self.rows.max(row + 1);
+        if self.in_region {
+            let start = self.region_starts.last_mut().unwrap();
+            *start = Some(start.map_or(row, |start| start.min(row)));
+        }
+    }
+}
+
+impl<F: Field> Assignment<F> for RowCounter {
+    fn enter_region<NR, N>(&mut self, name: N) where NR: Into<String>, N: FnOnce() -> NR {
+        self.region_starts.push(None);
+        self.in_region = true;
+        self.region_name = name().into();
+    }
+
+    fn exit_region(&mut self) {
+        self.in_region = false;
+    }
+
+    fn enable_selector<A, AR>(&mut self, _: A, _: &Selector, row: usize) -> Result<(), Error>
+    where A: FnOnce() -> AR, AR: Into<String> {
+        self.touch(row);
+        Ok(())
+    }
+
+    // witnesses are never looked at, so neither are instance values
+    fn query_instance(&self, _: Column<Instance>, _: usize) -> Result<Option<F>, Error> {
+        Ok(None)
+    }
+
+    fn assign_advice<V, VR, A, AR>(&mut self, name: A, column: Column<Advice>, row: usize, _: V) -> Result<(), Error>
+    where V: FnOnce() -> Result<VR, Error>, VR: Into<Assigned<F>>, A: FnOnce() -> AR, AR: Into<String> {
+        self.touch(row);
+        self.name(column.into(), row, name().into());
+        Ok(())
+    }
+
+    fn assign_fixed<V, VR, A, AR>(&mut self, name: A, column: Column<Fixed>, row: usize, _: V) -> Result<(), Error>
+    where V: FnOnce() -> Result<VR, Error>, VR: Into<Assigned<F>>, A: FnOnce() -> AR, AR: Into<String> {
+        self.touch(row);
+        self.name(column.into(), row, name().into());
+        Ok(())
+    }
+
+    fn copy(&mut self, left: Column<Any>, left_row: usize, right: Column<Any>, right_row: usize) -> Result<(), Error> {
+        self.copies.push(((left, left_row), (right, right_row)));
+        Ok(())
+    }
+
+    // pads lookup tables to the end of the column, that's not rows the circuit uses
+    fn fill_from_row(&mut self, _: Column<Fixed>, _: usize, _: Option<Assigned<F>>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn push_namespace<NR, N>(&mut self, _: N) where NR: Into<String>, N: FnOnce() -> NR {}
+
+    fn pop_namespace(&mut self, _: Option<String>) {}
+}
+
+// The columns a ConstraintSystem has enable_constant'ed. halo2 0.1 keeps the list private
+// but the floor planner needs it to place constants, so it's read back from the Debug output,
+// and the handles are recreated by allocating fixed columns until the index is reached
+fn constant_columns<F: FieldExt>(cs: &ConstraintSystem<F>) -> Vec<Column<Fixed>> {
+    let debug = format!("{:?}", cs);
+    let list = debug.split("constants: [").nth(1).and_then(|rest| rest.split(']').next()).unwrap_or("");
+    let indices: Vec<usize> = list.split("index: ").skip(1)
+        .filter_map(|rest| rest.split(',').next()?.trim().parse().ok())
+        .collect();
+
+    let mut fresh = ConstraintSystem::<F>::default();
+    let fixed: Vec<_> = (0..=indices.iter().copied().max().unwrap_or(0)).map(|_| fresh.fixed_column()).collect();
+    indices.into_iter().map(|i| fixed[i]).collect()
+}
+
+// How many rows synthesis actually assigns to, from the top of the circuit down to the last
+// row anything touches (constants included). Runs the circuit's floor planner without
+// any witnesses, so the circuit doesn't have to be satisfied
+pub fn rows_used<F: FieldExt, C: Circuit<F>>(circuit: &C) -> usize {
+    count_rows(circuit).rows
+}
+
+// First row of every region, by region index (same numbering as MockProver's failures).
+// None for a region that assigned nothing
+pub fn region_starts<F: FieldExt, C: Circuit<F>>(circuit: &C) -> Vec<Option<usize>> {
+    count_rows(circuit).region_starts
+}
+
+// One line per copy constraint, "<cell> = <cell>" in the order synthesis made them. A cell is
+// its column and row, and the region and annotation it was assigned with (instance cells
+// have neither). halo2 0.1 keeps MockProver's permutation private, so like region_starts
+// this comes from a dry run of the floor planner
+pub fn dump_permutations<F: FieldExt, C: Circuit<F>>(circuit: &C) -> String {
+    let counter = count_rows(circuit);
+    let cell = |(column, row): CellRef| {
+        let kind = match column.column_type() {
+            Any::Advice => "advice",
+            Any::Fixed => "fixed",
+            Any::Instance => "instance",
+        };
+        // the index is private too, but in the Debug output
+        let debug = format!("{:?}", column);
+        let index = debug.split("index: ").nth(1).and_then(|rest| rest.split(',').next()).unwrap_or("?");
+        match counter.names.get(&(column, row)) {
+            Some(name) => format!("{}[{}]@{} ({})", kind, index, row, name),
+            None => format!("{}[{}]@{}", kind, index, row),
+        }
+    };
+    counter.copies.iter()
+        .map(|&(left, right)| format!("{} = {}\n", cell(left), cell(right)))
+        .collect()
+}
+
+fn count_rows<F: FieldExt, C: Circuit<F>>(circuit: &C) -> RowCounter {
+    let mut cs = ConstraintSystem::default();
+    let config = C::configure(&mut cs);
+    let mut counter = RowCounter::default();
+    C::FloorPlanner::synthesize(&mut counter, circuit, config, constant_columns(&cs))
+        .expect("synthesis failed");
+    counter
+}
+
+// Row usage of a FibonacciCircuit at its smallest k
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowReport {
+    pub k: u32,
+    pub rows_used: usize,
+    // 2^k minus what halo2 reserves for blinding
+    pub usable_rows: usize,
+}
+
+impl fmt::Display for RowReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "k:           {} ({} rows)", self.k, 1usize << self.k)?;
+        writeln!(f, "usable rows: {}", self.usable_rows)?;
+        write!(f, "rows used:   {} ({}% of usable)", self.rows_used, self.rows_used * 100 / self.usable_rows.max(1))
+    }
+}
+
+pub fn analyze<F: FieldExt>(circuit: &FibonacciCircuit<F>) -> RowReport {
+    let k = circuit.min_k();
+    RowReport { k, rows_used: rows_used(circuit), usable_rows: usable_rows::<F>(k) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use halo2_proofs::{pasta::{EqAffine, Fp}, poly::commitment::Params};
+
+    use super::*;
+    use crate::{dev::SelfVerifying, prover::{keygen, prove, ProofRng}};
+
+    fn proof(seed: u64) -> Vec<u8> {
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+        let params = Params::<EqAffine>::new(circuit.min_k());
+        let pk = keygen(&params, &circuit).unwrap();
+        let public = circuit.expected_public().remove(0);
+        prove(&params, &pk, circuit, &public, ProofRng::seeded(seed)).unwrap()
+    }
+
+    #[test]
+    fn inspects_a_proof_file() {
+        let path = std::env::temp_dir().join(format!("halo2-circuits-inspect-{}.proof", std::process::id()));
+        fs::write(&path, proof(1)).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        let info = inspect_proof(&bytes);
+        assert_eq!(info.len as u64, fs::metadata(&path).unwrap().len());
+        fs::remove_file(&path).unwrap();
+
+        // the same proof hashes the same on every run, another one doesn't
+        assert_eq!(info, inspect_proof(&proof(1)));
+        assert_eq!(info.hash.len(), 64);
+        assert_ne!(info.hash, inspect_proof(&proof(2)).hash);
+    }
+
+    #[test]
+    fn f9_uses_one_row_per_add() {
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+        // f(2)..=f(9)
+        assert_eq!(rows_used(&circuit), 8);
+        assert_eq!(rows_used(&FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 10)), 9);
+
+        let report = analyze(&circuit);
+        assert_eq!((report.k, report.rows_used, report.usable_rows), (4, 8, usable_rows::<Fp>(4)));
+        assert!(report.to_string().ends_with(&format!("rows used:   8 ({}% of usable)", 800 / report.usable_rows)));
+    }
+
+    #[test]
+    fn f9_copies_link_each_row_to_the_one_before() {
+        let dump = dump_permutations(&FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9));
+        let lines: Vec<_> = dump.lines().collect();
+        assert!(lines.contains(&"advice[0]@1 (next row: a) = advice[1]@0 (first row: b)"), "{}", dump);
+        for row in 1..8 {
+            let b = format!("advice[1]@{} (next row: b) = advice[2]@{} ", row, row - 1);
+            assert!(lines.iter().any(|line| line.starts_with(&b)), "no {} in\n{}", b, dump);
+            // the previous row's b is a copy too, a goes back to the c it came from
+            if row > 1 {
+                let a = format!("advice[0]@{} (next row: a) = advice[2]@{} ", row, row - 2);
+                assert!(lines.iter().any(|line| line.starts_with(&a)), "no {} in\n{}", a, dump);
+            }
+        }
+        assert_eq!(lines.last(), Some(&"advice[2]@7 (next row: c) = instance[0]@2"));
+    }
+}
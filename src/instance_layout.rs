@@ -0,0 +1,149 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*
+};
+
+use crate::fibonacci::ACell;
+
+// Which instance row holds which public value, by name. The same layout wires the cells
+// in synthesize (`expose`) and orders the values for the prover (`public`), so the two
+// can't drift apart. Columns are indices into the instance columns the circuit configured
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstanceLayout {
+    // name, column, row
+    slots: Vec<(String, usize, usize)>,
+}
+
+impl InstanceLayout {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The next free row of column 0
+    pub fn slot(self, name: &str) -> Self {
+        let row = self.slots.iter().filter(|(_, column, _)| *column == 0).map(|(_, _, row)| row + 1).max().unwrap_or(0);
+        self.slot_at(name, 0, row)
+    }
+
+    // Panics if the name or the position is already taken
+    pub fn slot_at(mut self, name: &str, column: usize, row: usize) -> Self {
+        for (other, other_column, other_row) in &self.slots {
+            assert!(other != name, "instance slot {} defined twice", name);
+            assert!((*other_column, *other_row) != (column, row),
+                "{} and {} both at column {} row {}", other, name, column, row);
+        }
+        self.slots.push((name.to_string(), column, row));
+        self
+    }
+
+    // Column index and row of a slot, panics on a name that isn't in the layout
+    pub fn position(&self, name: &str) -> (usize, usize) {
+        self.slots.iter()
+            .find(|(other, _, _)| other == name)
+            .map(|(_, column, row)| (*column, *row))
+            .unwrap_or_else(|| panic!("no instance slot {}", name))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.slots.iter().map(|(name, _, _)| name.as_str())
+    }
+
+    // Constrains the cell to the slot's row
+    pub fn expose<F: FieldExt>(&self, mut layouter: impl Layouter<F>, instances: &[Column<Instance>],
+        name: &str, cell: &ACell<F>) -> Result<(), Error> {
+        let (column, row) = self.position(name);
+        layouter.constrain_instance(cell.0.cell(), instances[column], row)
+    }
+
+    // One vector per instance column, each value at its slot's row. Rows between slots
+    // are 0, panics unless every slot has exactly one value
+    pub fn public<F: FieldExt>(&self, values: &[(&str, F)]) -> Vec<Vec<F>> {
+        let columns = self.slots.iter().map(|(_, column, _)| column + 1).max().unwrap_or(0);
+        let mut public = vec![vec![]; columns];
+        for (name, column, row) in &self.slots {
+            let mut matching = values.iter().filter(|(other, _)| other == name);
+            let value = matching.next().unwrap_or_else(|| panic!("no value for instance slot {}", name)).1;
+            assert!(matching.next().is_none(), "more than one value for instance slot {}", name);
+
+            let rows: &mut Vec<F> = &mut public[*column];
+            if rows.len() <= *row {
+                rows.resize(row + 1, F::zero());
+            }
+            rows[*row] = value;
+        }
+        for (name, _) in values {
+            assert!(self.slots.iter().any(|(other, _, _)| other == name), "no instance slot {}", name);
+        }
+        public
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::layouter_ext::LayouterExt;
+
+    fn layout() -> InstanceLayout {
+        InstanceLayout::new().slot("a").slot("b").slot_at("checkpoint", 1, 2).slot("output")
+    }
+
+    #[test]
+    fn public_follows_the_slots() {
+        let public = layout().public(&[("output", Fp::from(55)), ("checkpoint", Fp::from(8)),
+            ("a", Fp::one()), ("b", Fp::from(2))]);
+        assert_eq!(public, vec![
+            vec![Fp::one(), Fp::from(2), Fp::from(55)],
+            vec![Fp::zero(), Fp::zero(), Fp::from(8)],
+        ]);
+        assert_eq!(layout().names().collect::<Vec<_>>(), ["a", "b", "checkpoint", "output"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "a and b both at column 0 row 0")]
+    fn rejects_two_slots_in_one_row() {
+        InstanceLayout::new().slot("a").slot_at("b", 0, 0);
+    }
+
+    // 1, 2, 8 and 55 assigned and exposed through `layout`
+    #[derive(Clone)]
+    struct Exposed;
+
+    impl Circuit<Fp> for Exposed {
+        type Config = (Column<Advice>, [Column<Instance>; 2]);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let advice = meta.advice_column();
+            let instances = [meta.instance_column(), meta.instance_column()];
+            meta.enable_equality(advice);
+            for instance in instances {
+                meta.enable_equality(instance);
+            }
+            (advice, instances)
+        }
+
+        fn synthesize(&self, (advice, instances): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            for (name, value) in [("a", 1), ("b", 2), ("checkpoint", 8), ("output", 55)] {
+                let cell = layouter.assign_value(name, advice, Some(Fp::from(value)))?;
+                layout().expose(layouter.namespace(|| name), &instances, name, &cell)?;
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn public_matches_the_wiring() {
+        let values = |output| layout().public(&[("a", Fp::one()), ("b", Fp::from(2)),
+            ("checkpoint", Fp::from(8)), ("output", Fp::from(output))]);
+        crate::assert_circuit!(Exposed, 4, values(55));
+        crate::assert_circuit_fails!(Exposed, 4, values(56), VerifyFailure::Permutation { .. });
+    }
+}
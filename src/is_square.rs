@@ -0,0 +1,129 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    dev::find_min_k,
+    layouter_ext::LayouterExt,
+    mul::{MulChip, MulConfig},
+};
+
+// The root of a u64 square fits here. -r = p - r doesn't, so the range check leaves only
+// the canonical root
+pub const SQUARE_ROOT_BITS: usize = 32;
+
+// Public y is a square: the witnessed root r is range checked and r * r goes through the
+// mul gate, copy constrained to y
+#[derive(Debug, Clone)]
+pub struct IsSquareConfig {
+    pub mul: MulConfig,
+    pub bits: BitsConfig,
+    pub instance: Column<Instance>,
+}
+
+// Public y at instance row 0, the witness root is isqrt(y) unless root replaces it, see
+// `with_root`. Both are None without a witness
+#[derive(Default, Clone)]
+pub struct IsSquareCircuit<F> {
+    pub y: Option<u64>,
+    pub root: Option<F>,
+}
+
+impl<F: FieldExt> IsSquareCircuit<F> {
+    pub fn new(y: u64) -> Self {
+        Self { y: Some(y), root: None }
+    }
+
+    // Any root, e.g. -r, for checking the range check rejects it
+    pub fn with_root(y: u64, root: F) -> Self {
+        Self { y: Some(y), root: Some(root) }
+    }
+}
+
+// floor(sqrt(y)), so the witness for a non-square is the root of the square below it
+pub fn isqrt(y: u64) -> u64 {
+    // the float can be off by one either way near 2^64, u128 so squaring it can't overflow
+    let mut r = (y as f64).sqrt() as u128;
+    while r * r > y as u128 {
+        r -= 1;
+    }
+    while (r + 1) * (r + 1) <= y as u128 {
+        r += 1;
+    }
+    r as u64
+}
+
+impl<F: FieldExt> Circuit<F> for IsSquareCircuit<F> {
+    type Config = IsSquareConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::default()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        IsSquareConfig { mul: MulChip::configure(meta), bits: BitsChip::configure(meta), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let root = self.root.or_else(|| self.y.map(|y| F::from(isqrt(y))));
+        let r = layouter.assign_value("r", config.mul.advice[0], root)?;
+        BitsChip::construct(config.bits).decompose(layouter.namespace(|| "r range"), &r, SQUARE_ROOT_BITS)?;
+
+        let square = MulChip::construct(config.mul).square(layouter.namespace(|| "r * r"), &r)?;
+        layouter.constrain_instance(square.0.cell(), config.instance, 0)
+    }
+}
+
+// Mock proves that y is a perfect square
+pub fn prove_is_square(y: u64) -> Result<(), Vec<VerifyFailure>> {
+    prove_is_square_with(IsSquareCircuit::new(y))
+}
+
+pub fn prove_is_square_with(circuit: IsSquareCircuit<Fp>) -> Result<(), Vec<VerifyFailure>> {
+    let public = vec![vec![Fp::from(circuit.y.expect("y"))]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proves_25_and_144() {
+        assert_eq!((isqrt(25), isqrt(144)), (5, 12));
+        assert_eq!(prove_is_square(25), Ok(()));
+        assert_eq!(prove_is_square(144), Ok(()));
+    }
+
+    #[test]
+    fn rejects_26() {
+        // the honest witness is 5, whose square is 25
+        let failures = prove_is_square(26).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+
+    #[test]
+    fn range_check_rules_out_the_other_root() {
+        // (-5)^2 = 25 as well, but -5 is p - 5 and far wider than 32 bits
+        assert_eq!(prove_is_square_with(IsSquareCircuit::with_root(25, Fp::from(5))), Ok(()));
+        assert!(prove_is_square_with(IsSquareCircuit::with_root(25, -Fp::from(5))).is_err());
+    }
+
+    #[test]
+    fn real_proofs_agree() {
+        let circuit = IsSquareCircuit::<Fp>::new(144);
+        assert_eq!(circuit.without_witnesses().y, None);
+        assert!(crate::dev::full_roundtrip(&circuit, 7, vec![Fp::from(144)]));
+        assert!(!crate::dev::full_roundtrip(&circuit, 7, vec![Fp::from(145)]));
+    }
+}
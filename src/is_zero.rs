@@ -0,0 +1,63 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+// The usual is-zero gadget: witness value_inv and use 1 - value * value_inv,
+// which is 1 exactly when value is 0 (and value_inv is then free, we assign 0)
+#[derive(Debug, Clone)]
+pub struct IsZeroConfig<F: FieldExt> {
+    pub value_inv: Column<Advice>,
+    is_zero_expr: Expression<F>,
+}
+
+impl<F: FieldExt> IsZeroConfig<F> {
+    // 1 if the value is zero, 0 otherwise, for use in other gates
+    pub fn expr(&self) -> Expression<F> {
+        self.is_zero_expr.clone()
+    }
+}
+
+pub struct IsZeroChip<F: FieldExt> {
+    config: IsZeroConfig<F>,
+}
+
+impl<F: FieldExt> IsZeroChip<F> {
+    pub fn construct(config: IsZeroConfig<F>) -> Self {
+        Self { config }
+    }
+
+    // q_enable and value are expressions so the gadget can sit on any row layout of the caller
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        q_enable: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value: impl FnOnce(&mut VirtualCells<'_, F>) -> Expression<F>,
+        value_inv: Column<Advice>,
+    ) -> IsZeroConfig<F> {
+        let mut is_zero_expr = Expression::Constant(F::zero());
+
+        meta.create_gate("is zero", |meta| {
+            //
+            // value | value_inv | 1 - value * value_inv | value * (1 - value * value_inv)
+            //   x   |   1/x     |          0            |            0
+            //   0   |    0      |          1            |            0
+            //
+            let value = value(meta);
+            let q_enable = q_enable(meta);
+            let value_inv = meta.query_advice(value_inv, Rotation::cur());
+
+            is_zero_expr = Expression::Constant(F::one()) - value.clone() * value_inv;
+            vec![q_enable * value * is_zero_expr.clone()]
+        });
+
+        IsZeroConfig { value_inv, is_zero_expr }
+    }
+
+    pub fn assign(&self, region: &mut Region<'_, F>, offset: usize, value: Option<F>) -> Result<(), Error> {
+        let value_inv = value.map(|value| value.invert().unwrap_or(F::zero()));
+        region.assign_advice(|| "value inv", self.config.value_inv, offset,
+            || value_inv.ok_or(Error::Synthesis))?;
+        Ok(())
+    }
+}
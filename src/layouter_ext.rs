@@ -0,0 +1,87 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*
+};
+
+use crate::fibonacci::ACell;
+
+// One liners for regions holding a single cell, which would otherwise each be an
+// assign_region with a closure around one assignment. The region is named after the cell
+pub trait LayouterExt<F: FieldExt>: Layouter<F> {
+    fn assign_value(&mut self, name: &str, column: Column<Advice>, value: Option<F>) -> Result<ACell<F>, Error> {
+        self.assign_region(|| name, |mut region| {
+            region.assign_advice(|| name, column, 0, || value.ok_or(Error::Synthesis)).map(ACell)
+        })
+    }
+
+    // Copies instance row `row` into the column
+    fn assign_from_instance(&mut self, name: &str, instance: Column<Instance>, row: usize, column: Column<Advice>) -> Result<ACell<F>, Error> {
+        self.assign_region(|| name, |mut region| {
+            region.assign_advice_from_instance(|| name, instance, row, column, 0).map(ACell)
+        })
+    }
+}
+
+impl<F: FieldExt, L: Layouter<F>> LayouterExt<F> for L {}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::fibonacci::cell_value;
+
+    // 7 through assign_value, exposed at instance row 0, then copied back in from there.
+    // The values both cells carried during synthesis are kept in `seen`
+    #[derive(Default)]
+    struct SevenCircuit {
+        seen: RefCell<Vec<Option<Fp>>>,
+    }
+
+    impl Clone for SevenCircuit {
+        fn clone(&self) -> Self {
+            Self::default()
+        }
+    }
+
+    impl Circuit<Fp> for SevenCircuit {
+        type Config = (Column<Advice>, Column<Instance>);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let (advice, instance) = (meta.advice_column(), meta.instance_column());
+            meta.enable_equality(advice);
+            meta.enable_equality(instance);
+            (advice, instance)
+        }
+
+        fn synthesize(&self, (advice, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let seven = layouter.assign_value("seven", advice, Some(Fp::from(7)))?;
+            layouter.constrain_instance(seven.0.cell(), instance, 0)?;
+            let copied = layouter.assign_from_instance("copied", instance, 0, advice)?;
+            *self.seen.borrow_mut() = vec![cell_value(&seven), cell_value(&copied)];
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn assigned_cell_carries_its_value() {
+        let circuit = SevenCircuit::default();
+        crate::assert_circuit!(circuit, 4, vec![vec![Fp::from(7)]]);
+        assert_eq!(*circuit.seen.borrow(), vec![Some(Fp::from(7)); 2]);
+    }
+
+    #[test]
+    fn assigned_cell_is_copy_constrained() {
+        crate::assert_circuit_fails!(SevenCircuit::default(), 4, vec![vec![Fp::from(8)]],
+            VerifyFailure::Permutation { .. });
+    }
+}
@@ -0,0 +1,48 @@
+pub mod arith;
+pub mod bits;
+pub mod checksum;
+pub mod collatz;
+pub mod compact;
+pub mod convergent;
+pub mod ctz;
+pub mod cursor;
+pub mod dev;
+pub mod digit_sum;
+pub mod divisible;
+pub mod divmod;
+pub mod error;
+pub mod fib_table;
+pub mod fibonacci;
+pub mod index_of;
+pub mod inspect;
+pub mod instance_layout;
+pub mod is_square;
+pub mod is_zero;
+pub mod layouter_ext;
+pub mod linear;
+pub mod lucas;
+pub mod matrix_pow;
+pub mod meet;
+pub mod mod_inverse;
+pub mod moving_avg;
+pub mod mul;
+pub mod non_member;
+pub mod phi;
+pub mod pool;
+pub mod popcount;
+pub mod prelude;
+pub mod prime;
+#[cfg(feature = "profile")]
+pub mod profile;
+#[cfg(feature = "debug-provenance")]
+pub mod provenance;
+pub mod prover;
+pub mod pythagorean;
+pub mod range_table;
+pub mod sequence;
+pub mod sub;
+pub mod subset_sum;
+pub mod sum_identity;
+pub mod sum_of_squares;
+pub mod triangular;
+pub mod zeckendorf;
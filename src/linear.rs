@@ -0,0 +1,143 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    cursor::RowCursor,
+    fibonacci::{cell_value, ACell},
+};
+
+// sum(c_i * x_i) = t for public coefficients c_i and target t, both baked in as fixed values.
+// One row per term with a running sum, the last sum is pinned to t
+#[derive(Debug, Clone)]
+pub struct LinearConfig {
+    pub value: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub coeff: Column<Fixed>,
+    pub first: Selector,
+    pub next: Selector,
+}
+
+pub struct LinearChip<F: FieldExt> {
+    config: LinearConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> LinearChip<F> {
+    pub fn construct(config: LinearConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> LinearConfig {
+        let value = meta.advice_column();
+        let acc = meta.advice_column();
+        let coeff = meta.fixed_column();
+        let first = meta.selector();
+        let next = meta.selector();
+        // the target
+        let constant = meta.fixed_column();
+
+        meta.enable_constant(constant);
+        meta.enable_equality(value);
+        meta.enable_equality(acc);
+
+        meta.create_gate("linear first", |meta| {
+            // value | acc           | coeff | selector
+            //  x0   | c0 * x0       |  c0   | first
+            //  x1   | acc + c1 * x1 |  c1   | next
+            let first = meta.query_selector(first);
+            let x = meta.query_advice(value, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let c = meta.query_fixed(coeff, Rotation::cur());
+            vec![first * (acc - c * x)]
+        });
+
+        meta.create_gate("linear next", |meta| {
+            let next = meta.query_selector(next);
+            let x = meta.query_advice(value, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let c = meta.query_fixed(coeff, Rotation::cur());
+            vec![next * (acc_cur - (acc_prev + c * x))]
+        });
+
+        LinearConfig { value, acc, coeff, first, next }
+    }
+
+    pub fn linear_combination_eq(&self, mut layouter: impl Layouter<F>, cells: &[ACell<F>], coeffs: &[F], target: F) -> Result<(), Error> {
+        assert_eq!(cells.len(), coeffs.len(), "one coefficient per cell");
+        assert!(!cells.is_empty(), "empty linear combination");
+
+        layouter.assign_region(|| "linear combination", |mut region| {
+            let mut cursor = RowCursor::new(&mut region);
+            let mut acc_val = Some(F::zero());
+            let mut acc_cell = None;
+            for (row, (cell, coeff)) in cells.iter().zip(coeffs).enumerate() {
+                if row > 0 {
+                    cursor.next_row();
+                    cursor.enable(self.config.next)?;
+                } else {
+                    cursor.enable(self.config.first)?;
+                }
+                cursor.assign_fixed("coeff", self.config.coeff, *coeff)?;
+                cursor.copy("x", self.config.value, cell)?;
+
+                acc_val = acc_val.and_then(|acc| cell_value(cell).map(|x| acc + *coeff * x));
+                acc_cell = Some(cursor.assign("acc", self.config.acc, acc_val)?);
+            }
+            cursor.region().constrain_constant(acc_cell.unwrap().0.cell(), target)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::fibonacci::{FiboChip, FiboConfig, FibonacciCircuit};
+
+    // 2 * f(5) + 3 * f(6) = target over the f(9) trace from the public seeds 1, 1
+    #[derive(Clone)]
+    struct LinearCircuit {
+        target: u64,
+    }
+
+    impl Circuit<Fp> for LinearCircuit {
+        type Config = (FiboConfig, LinearConfig);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            (FiboChip::configure(meta, instance), LinearChip::configure(meta))
+        }
+
+        fn synthesize(&self, (fibo, config): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let trace = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+            let cells = trace.compute_all(&FiboChip::construct(fibo), layouter.namespace(|| "trace"))?;
+            LinearChip::construct(config).linear_combination_eq(layouter.namespace(|| "2 f(5) + 3 f(6)"),
+                &cells[5..=6], &[Fp::from(2), Fp::from(3)], Fp::from(self.target))
+        }
+    }
+
+    #[test]
+    fn accepts_the_right_target() {
+        // 2 * 8 + 3 * 13
+        crate::assert_circuit!(LinearCircuit { target: 55 }, 4, vec![vec![Fp::one(), Fp::one()]]);
+    }
+
+    #[test]
+    fn rejects_a_wrong_target() {
+        crate::assert_circuit_fails!(LinearCircuit { target: 54 }, 4, vec![vec![Fp::one(), Fp::one()]],
+            VerifyFailure::Permutation { .. });
+    }
+}
@@ -0,0 +1,209 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::{dev::find_min_k, fibonacci::{fib_native, ACell}};
+
+// f(n) and the Lucas numbers L(n) side by side, one row per n, for f(0) = 0, f(1) = 1,
+// L(0) = 2, L(1) = 1 pinned to constants. Both columns follow the Fibonacci recurrence, and
+// the cross check L(n) = f(n-1) + f(n+1) ties every row of one to the other. It follows
+// from the other constraints, so it's a redundancy check: a forged f(n) breaks it too
+#[derive(Debug, Clone)]
+pub struct LucasConfig {
+    pub f: Column<Advice>,
+    pub l: Column<Advice>,
+    pub step: Selector,
+    pub cross: Selector,
+}
+
+pub struct LucasChip<F: FieldExt> {
+    config: LucasConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> LucasChip<F> {
+    pub fn construct(config: LucasConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> LucasConfig {
+        let f = meta.advice_column();
+        let l = meta.advice_column();
+        let step = meta.selector();
+        let cross = meta.selector();
+        let constant = meta.fixed_column();
+
+        meta.enable_constant(constant);
+        meta.enable_equality(f);
+        meta.enable_equality(l);
+
+        meta.create_gate("lucas step", |meta| {
+            //   f    |   l    | selector
+            // f(n-1) | L(n-1) |
+            // f(n)   | L(n)   | step, cross
+            // f(n+1) | L(n+1) |
+            let s = meta.query_selector(step);
+            let [f_prev, f_cur, f_next] = [Rotation::prev(), Rotation::cur(), Rotation::next()]
+                .map(|rotation| meta.query_advice(f, rotation));
+            let [l_prev, l_cur, l_next] = [Rotation::prev(), Rotation::cur(), Rotation::next()]
+                .map(|rotation| meta.query_advice(l, rotation));
+            vec![
+                s.clone() * (f_next - f_cur - f_prev),
+                s * (l_next - l_cur - l_prev),
+            ]
+        });
+
+        meta.create_gate("lucas cross", |meta| {
+            let s = meta.query_selector(cross);
+            let f_prev = meta.query_advice(f, Rotation::prev());
+            let f_next = meta.query_advice(f, Rotation::next());
+            let l_cur = meta.query_advice(l, Rotation::cur());
+            vec![s * (l_cur - f_prev - f_next)]
+        });
+
+        LucasConfig { f, l, step, cross }
+    }
+
+    // Rows 0..=steps + 1, returns f(steps) and L(steps). forged replaces the witness of one
+    // f(n), n >= 2
+    pub fn assign(&self, mut layouter: impl Layouter<F>, steps: usize, forged: Option<(usize, F)>) -> Result<(ACell<F>, ACell<F>), Error> {
+        assert!(steps >= 1, "the cross check starts at L(1)");
+
+        layouter.assign_region(|| "lucas", |mut region| {
+            let (mut f_prev, mut f_cur) = (F::zero(), F::one());
+            let (mut l_prev, mut l_cur) = (F::from(2), F::one());
+            let mut cells = vec![];
+            for n in 0..=steps + 1 {
+                if (1..=steps).contains(&n) {
+                    self.config.step.enable(&mut region, n)?;
+                    self.config.cross.enable(&mut region, n)?;
+                }
+
+                let (f, l) = match n {
+                    0 => (
+                        region.assign_advice_from_constant(|| "f(0)", self.config.f, 0, F::zero()),
+                        region.assign_advice_from_constant(|| "L(0)", self.config.l, 0, F::from(2)),
+                    ),
+                    1 => (
+                        region.assign_advice_from_constant(|| "f(1)", self.config.f, 1, F::one()),
+                        region.assign_advice_from_constant(|| "L(1)", self.config.l, 1, F::one()),
+                    ),
+                    _ => {
+                        (f_prev, f_cur) = (f_cur, f_prev + f_cur);
+                        (l_prev, l_cur) = (l_cur, l_prev + l_cur);
+                        let f = match forged {
+                            Some((m, value)) if m == n => value,
+                            _ => f_cur,
+                        };
+                        (
+                            region.assign_advice(|| "f", self.config.f, n, || Ok(f)),
+                            region.assign_advice(|| "L", self.config.l, n, || Ok(l_cur)),
+                        )
+                    }
+                };
+                cells.push((ACell(f?), ACell(l?)));
+            }
+            Ok(cells.swap_remove(steps))
+        })
+    }
+}
+
+// L(n) outside the circuit
+pub fn lucas_native(n: usize) -> u64 {
+    let (mut a, mut b) = (2u64, 1u64);
+    for _ in 0..n {
+        (a, b) = (b, a + b);
+    }
+    a
+}
+
+#[derive(Debug, Clone)]
+pub struct LucasCircuitConfig {
+    pub lucas: LucasConfig,
+    pub instance: Column<Instance>,
+}
+
+// f(steps) and L(steps) public, at instance rows 0 and 1
+#[derive(Default, Clone)]
+pub struct LucasCircuit<F> {
+    pub steps: usize,
+    pub forged: Option<(usize, u64)>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> LucasCircuit<F> {
+    pub fn new(steps: usize) -> Self {
+        Self { steps, forged: None, _marker: PhantomData }
+    }
+
+    // f(n) witnessed as value instead, for checking the cross check catches it
+    pub fn forged(steps: usize, n: usize, value: u64) -> Self {
+        assert!((2..=steps + 1).contains(&n), "only f(2)..f(steps + 1) are witnessed");
+        Self { forged: Some((n, value)), ..Self::new(steps) }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for LucasCircuit<F> {
+    type Config = LucasCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.steps)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        LucasCircuitConfig { lucas: LucasChip::configure(meta), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let forged = self.forged.map(|(n, value)| (n, F::from(value)));
+        let (f, l) = LucasChip::construct(config.lucas).assign(layouter.namespace(|| "lucas"), self.steps, forged)?;
+        layouter.constrain_instance(f.0.cell(), config.instance, 0)?;
+        layouter.constrain_instance(l.0.cell(), config.instance, 1)
+    }
+}
+
+// Mock proves f(steps) and L(steps), f from the standard seeds 0, 1
+pub fn prove_lucas(circuit: LucasCircuit<Fp>) -> Result<(), Vec<VerifyFailure>> {
+    let f = fib_native(Fp::zero(), Fp::one(), circuit.steps);
+    let public = vec![vec![f, Fp::from(lucas_native(circuit.steps))]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cross_check_holds_up_to_10() {
+        assert_eq!(lucas_native(10), 123);
+        // f(10) = 55
+        assert_eq!(prove_lucas(LucasCircuit::new(10)), Ok(()));
+    }
+
+    #[test]
+    fn cross_check_catches_a_forged_term() {
+        // f(5) = 5 witnessed as 6
+        let failures = prove_lucas(LucasCircuit::forged(10, 5, 6)).unwrap_err();
+        assert!(failures.iter().any(|failure| failure.to_string().contains("lucas cross")));
+    }
+
+    #[test]
+    fn real_proofs_agree() {
+        let public = vec![Fp::from(55), Fp::from(123)];
+        let forged = LucasCircuit::forged(10, 5, 6);
+        assert!(forged.without_witnesses().forged.is_none());
+        assert!(crate::dev::full_roundtrip(&LucasCircuit::new(10), 5, public.clone()));
+        assert!(!crate::dev::full_roundtrip(&forged, 5, public));
+    }
+}
@@ -0,0 +1,185 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    arith::{ArithChip, ArithConfig},
+    dev::find_min_k,
+    fibonacci::ACell,
+};
+
+// The four entries of a 2x2 matrix, row by row
+pub type Matrix<T> = [[T; 2]; 2];
+
+// M^n by repeated squaring on the arith add and mul gates, every entry a cell of its own.
+// M is pinned to constants, so the entries of the power are fixed by M and n alone.
+// Each matrix product is 8 muls and 4 adds, at most 2 * log2(n) products
+#[derive(Debug, Clone)]
+pub struct MatrixPowConfig {
+    pub arith: ArithConfig,
+    pub constant: Column<Fixed>,
+}
+
+pub struct MatrixPowChip<F: FieldExt> {
+    config: MatrixPowConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MatrixPowChip<F> {
+    pub fn construct(config: MatrixPowConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MatrixPowConfig {
+        let constant = meta.fixed_column();
+        meta.enable_constant(constant);
+        MatrixPowConfig { arith: ArithChip::configure(meta, true), constant }
+    }
+
+    pub fn matrix_pow(&self, mut layouter: impl Layouter<F>, m: Matrix<F>, n: u64) -> Result<Matrix<ACell<F>>, Error> {
+        // M^0 is the identity, for n > 0 the identity never gets multiplied in
+        let identity = [[F::one(), F::zero()], [F::zero(), F::one()]];
+        let constants = if n == 0 { identity } else { m };
+        let mut base = layouter.assign_region(|| "matrix", |mut region| {
+            let mut row = 0;
+            let mut entry = |value: F| {
+                row += 1;
+                region.assign_advice_from_constant(|| "entry", self.config.arith.advice[0], row - 1, value).map(ACell)
+            };
+            Ok([[entry(constants[0][0])?, entry(constants[0][1])?], [entry(constants[1][0])?, entry(constants[1][1])?]])
+        })?;
+        if n == 0 {
+            return Ok(base);
+        }
+
+        // bits of n from the lowest, squaring the base in between
+        let mut acc: Option<Matrix<ACell<F>>> = None;
+        let mut bits = n;
+        loop {
+            if bits & 1 == 1 {
+                acc = Some(match acc {
+                    Some(acc) => self.mat_mul(layouter.namespace(|| "multiply"), &acc, &base)?,
+                    None => base.clone(),
+                });
+            }
+            bits >>= 1;
+            if bits == 0 {
+                break;
+            }
+            base = self.mat_mul(layouter.namespace(|| "square"), &base, &base)?;
+        }
+        Ok(acc.unwrap())
+    }
+
+    // c_ij = a_i0 * b_0j + a_i1 * b_1j
+    fn mat_mul(&self, mut layouter: impl Layouter<F>, a: &Matrix<ACell<F>>, b: &Matrix<ACell<F>>) -> Result<Matrix<ACell<F>>, Error> {
+        let arith = ArithChip::construct(self.config.arith.clone());
+        let mut entry = |i: usize, j: usize| {
+            let x = arith.mul(layouter.namespace(|| "a_i0 * b_0j"), &a[i][0], &b[0][j])?;
+            let y = arith.mul(layouter.namespace(|| "a_i1 * b_1j"), &a[i][1], &b[1][j])?;
+            arith.add(layouter.namespace(|| "c_ij"), &x, &y)
+        };
+        Ok([[entry(0, 0)?, entry(0, 1)?], [entry(1, 0)?, entry(1, 1)?]])
+    }
+}
+
+// The same power outside the circuit, in the field
+pub fn matrix_pow_native<F: FieldExt>(m: Matrix<F>, n: u64) -> Matrix<F> {
+    let mul = |a: Matrix<F>, b: Matrix<F>| {
+        let entry = |i: usize, j: usize| a[i][0] * b[0][j] + a[i][1] * b[1][j];
+        [[entry(0, 0), entry(0, 1)], [entry(1, 0), entry(1, 1)]]
+    };
+    let mut acc = [[F::one(), F::zero()], [F::zero(), F::one()]];
+    for _ in 0..n {
+        acc = mul(acc, m);
+    }
+    acc
+}
+
+#[derive(Debug, Clone)]
+pub struct MatrixPowCircuitConfig {
+    pub matrix: MatrixPowConfig,
+    pub instance: Column<Instance>,
+}
+
+// Fixed M and n, the four entries of M^n public, row by row
+#[derive(Default, Clone)]
+pub struct MatrixPowCircuit<F> {
+    pub m: Matrix<u64>,
+    pub n: u64,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MatrixPowCircuit<F> {
+    pub fn new(m: Matrix<u64>, n: u64) -> Self {
+        Self { m, n, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MatrixPowCircuit<F> {
+    type Config = MatrixPowCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        MatrixPowCircuitConfig { matrix: MatrixPowChip::configure(meta), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let m = self.m.map(|row| row.map(F::from));
+        let power = MatrixPowChip::construct(config.matrix).matrix_pow(layouter.namespace(|| "power"), m, self.n)?;
+        for (row, entry) in power.iter().flatten().enumerate() {
+            layouter.constrain_instance(entry.0.cell(), config.instance, row)?;
+        }
+        Ok(())
+    }
+}
+
+// Mock proves M^n = expected. The Fibonacci matrix [[1, 1], [1, 0]] to the n has
+// f(n + 1), f(n), f(n), f(n - 1) in it, for f(0) = 0 and f(1) = 1
+pub fn prove_matrix_pow(m: Matrix<u64>, n: u64, expected: Matrix<u64>) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = MatrixPowCircuit::<Fp>::new(m, n);
+    let public = vec![expected.iter().flatten().map(|&x| Fp::from(x)).collect()];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIB: Matrix<u64> = [[1, 1], [1, 0]];
+
+    #[test]
+    fn fibonacci_matrix_to_the_10th() {
+        // f(11), f(10), f(10), f(9) with f(0) = 0
+        let expected = [[89, 55], [55, 34]];
+        let native = matrix_pow_native(FIB.map(|row| row.map(Fp::from)), 10);
+        assert_eq!(native, expected.map(|row| row.map(Fp::from)));
+        assert_eq!(prove_matrix_pow(FIB, 10, expected), Ok(()));
+    }
+
+    #[test]
+    fn zeroth_power_is_the_identity() {
+        assert_eq!(prove_matrix_pow(FIB, 0, [[1, 0], [0, 1]]), Ok(()));
+    }
+
+    #[test]
+    fn rejects_the_wrong_power() {
+        // M^9
+        let failures = prove_matrix_pow(FIB, 10, [[55, 34], [34, 21]]).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+}
@@ -0,0 +1,174 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    dev::find_min_k,
+    fibonacci::{FiboChip, FiboConfig, FibonacciCircuit},
+};
+
+// Two sequences with their own public seeds meet: f1(i) = f2(j). Both traces go through
+// the same FiboChip one after the other (seeds kept out of compute_all, this circuit exposes
+// them itself) and the two cells get a plain copy constraint between them
+#[derive(Default, Clone)]
+pub struct MeetCircuit<F> {
+    pub seeds1: (Option<F>, Option<F>),
+    pub i: usize,
+    pub seeds2: (Option<F>, Option<F>),
+    pub j: usize,
+}
+
+impl<F: FieldExt> MeetCircuit<F> {
+    pub fn new(seeds1: (F, F), i: usize, seeds2: (F, F), j: usize) -> Self {
+        Self {
+            seeds1: (Some(seeds1.0), Some(seeds1.1)),
+            i,
+            seeds2: (Some(seeds2.0), Some(seeds2.1)),
+            j,
+        }
+    }
+
+    // a1, b1, a2, b2
+    pub fn public(&self) -> Vec<F> {
+        let seed = |s: Option<F>| s.expect("seeds are known");
+        vec![seed(self.seeds1.0), seed(self.seeds1.1), seed(self.seeds2.0), seed(self.seeds2.1)]
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for MeetCircuit<F> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { seeds1: (None, None), seeds2: (None, None), ..self.clone() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciCircuit::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let column = config.advice[0];
+        let chip = FiboChip::construct(config);
+
+        let first = FibonacciCircuit::new(self.seeds1.0, self.seeds1.1, self.i).with_private_seeds();
+        let cells1 = first.compute_all(&chip, layouter.namespace(|| "first sequence"))?;
+        let second = FibonacciCircuit::new(self.seeds2.0, self.seeds2.1, self.j).with_private_seeds();
+        let cells2 = second.compute_all(&chip, layouter.namespace(|| "second sequence"))?;
+
+        for (row, seed) in [&cells1[0], &cells1[1], &cells2[0], &cells2[1]].into_iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "seed"), seed, row)?;
+        }
+
+        // cells are f(0), f(1), .. so index i is f(i). The region gets a copy of f1(i) so it
+        // isn't empty, MockProver can't locate failures past an empty region
+        layouter.assign_region(|| "meet", |mut region| {
+            let meet = cells1[self.i].0.copy_advice(|| "f1(i)", &mut region, column, 0)?;
+            region.constrain_equal(meet.cell(), cells2[self.j].0.cell())
+        })
+    }
+}
+
+// Mock proves f1(i) = f2(j) for the sequences starting at seeds1 and seeds2
+pub fn prove_meet(seeds1: (Fp, Fp), i: usize, seeds2: (Fp, Fp), j: usize) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = MeetCircuit::new(seeds1, i, seeds2, j);
+    let public = vec![circuit.public()];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{pasta::EqAffine, poly::commitment::Params};
+
+    use super::*;
+    use crate::prover::{keygen, prove, verify, vk_bytes, ProofRng};
+
+    // MeetCircuit with the first trace written row by hand and its active flag cleared from
+    // offset `stop` on, so f1(stop + 1) is carried to f1(i)
+    #[derive(Clone)]
+    struct ForgedEarlyStop {
+        meet: MeetCircuit<Fp>,
+        stop: usize,
+    }
+
+    impl Circuit<Fp> for ForgedEarlyStop {
+        type Config = FiboConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            FibonacciCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let (meet, column) = (&self.meet, config.advice[0]);
+            let chip = FiboChip::construct(config);
+
+            let (a, mut b, mut c, mut active) = chip.assign_first_row(layouter.namespace(|| "first row"), meet.seeds1.0, meet.seeds1.1)?;
+            let seeds1 = [a, b.clone()];
+            for row in 1..meet.i - 1 {
+                let (next_c, next_active) = chip.assign_row(layouter.namespace(|| "next row"), &b, &c, &active, row < self.stop)?;
+                b = c;
+                c = next_c;
+                active = next_active;
+            }
+            let second = FibonacciCircuit::new(meet.seeds2.0, meet.seeds2.1, meet.j).with_private_seeds();
+            let cells2 = second.compute_all(&chip, layouter.namespace(|| "second sequence"))?;
+
+            for (row, seed) in [&seeds1[0], &seeds1[1], &cells2[0], &cells2[1]].into_iter().enumerate() {
+                chip.expose_public(layouter.namespace(|| "seed"), seed, row)?;
+            }
+            layouter.assign_region(|| "meet", |mut region| {
+                let copy = c.0.copy_advice(|| "f1(i)", &mut region, column, 0)?;
+                region.constrain_equal(copy.cell(), cells2[meet.j].0.cell())
+            })
+        }
+    }
+
+    fn seeds(a: u64, b: u64) -> (Fp, Fp) {
+        (Fp::from(a), Fp::from(b))
+    }
+
+    #[test]
+    fn sequences_meet_at_55() {
+        // 1, 1, 2, .. has f(9) = 55 and 2, 3, 5, .. has f(7) = 55
+        prove_meet(seeds(1, 1), 9, seeds(2, 3), 7).unwrap();
+        let circuit = MeetCircuit::new(seeds(1, 1), 9, seeds(2, 3), 7);
+        assert!(crate::dev::full_roundtrip(&circuit, 5, circuit.public()));
+    }
+
+    #[test]
+    fn rejects_sequences_that_miss() {
+        // f2(6) = 34
+        let failures = prove_meet(seeds(1, 1), 9, seeds(2, 3), 6).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+        let circuit = MeetCircuit::new(seeds(1, 1), 9, seeds(2, 3), 6);
+        assert!(!crate::dev::full_roundtrip(&circuit, 5, circuit.public()));
+    }
+
+    #[test]
+    fn rejects_a_first_trace_that_stops_early() {
+        // f1(9) = 55 but f2(4) = 13. Stopping the first trace after offset 4 carries
+        // f1(6) = 13 instead, which only verifies against the forged layout
+        let meet = MeetCircuit::new(seeds(1, 1), 9, seeds(2, 3), 4);
+        assert!(prove_meet(seeds(1, 1), 9, seeds(2, 3), 4).is_err());
+        let forged = ForgedEarlyStop { meet: meet.clone(), stop: 5 };
+        crate::assert_circuit!(forged.clone(), 5, vec![meet.public()]);
+
+        let params = Params::<EqAffine>::new(5);
+        let pk = keygen(&params, &meet).unwrap();
+        assert_ne!(vk_bytes(&keygen_vk(&params, &forged).unwrap()), vk_bytes(pk.get_vk()));
+        let proof = prove(&params, &pk, forged, &meet.public(), ProofRng::seeded(3)).unwrap();
+        assert!(verify(&params, pk.get_vk(), &meet.public(), &proof).is_err());
+    }
+}
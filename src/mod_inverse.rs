@@ -0,0 +1,206 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    dev::find_min_k,
+    fibonacci::{cell_value, ACell},
+    linear::{LinearChip, LinearConfig},
+    mul::{MulChip, MulConfig},
+};
+
+// Width of every operand, the products stay below 2^128 so nothing wraps around the modulus
+pub const MOD_INVERSE_BITS: usize = 64;
+
+// b = a^-1 mod m, by witnessing b and q with a * b = q * m + 1 and b < m (b + d = m - 1 for
+// a range checked d). Both products go through the mul gate, the two equations are linear
+// combinations with constant targets. a, b, q, d and m are range checked so the equation
+// holds over the integers, not just in the field. Only satisfiable when gcd(a, m) = 1
+#[derive(Debug, Clone)]
+pub struct ModInverseConfig {
+    pub mul: MulConfig,
+    pub linear: LinearConfig,
+    pub bits: BitsConfig,
+    pub instance: Column<Instance>,
+}
+
+pub struct ModInverseChip<F: FieldExt> {
+    config: ModInverseConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ModInverseChip<F> {
+    pub fn construct(config: ModInverseConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> ModInverseConfig {
+        meta.enable_equality(instance);
+        ModInverseConfig {
+            mul: MulChip::configure(meta),
+            linear: LinearChip::configure(meta),
+            bits: BitsChip::configure(meta),
+            instance,
+        }
+    }
+
+    // Returns b. Without an inverse b and q are witnessed as 0, and the circuit isn't satisfied
+    pub fn mod_inverse(&self, mut layouter: impl Layouter<F>, a: &ACell<F>, m: &ACell<F>) -> Result<ACell<F>, Error> {
+        let a_val = cell_value(a).map(|a| a.get_lower_128() as u64);
+        let m_val = cell_value(m).map(|m| m.get_lower_128() as u64);
+        let witness = a_val.and_then(|a| m_val.map(|m| {
+            match mod_inverse_native(a, m) {
+                Some(b) => {
+                    let q = ((a as u128 * b as u128 - 1) / m as u128) as u64;
+                    (b, q, m - 1 - b)
+                }
+                None => (0, 0, m.saturating_sub(1)),
+            }
+        }));
+
+        let [col_b, col_q, col_d] = self.config.mul.advice;
+        let (b, q, d) = layouter.assign_region(|| "inverse witness", |mut region| {
+            let b = region.assign_advice(|| "b", col_b, 0,
+                || witness.map(|w| F::from(w.0)).ok_or(Error::Synthesis)).map(ACell)?;
+            let q = region.assign_advice(|| "q", col_q, 0,
+                || witness.map(|w| F::from(w.1)).ok_or(Error::Synthesis)).map(ACell)?;
+            let d = region.assign_advice(|| "d", col_d, 0,
+                || witness.map(|w| F::from(w.2)).ok_or(Error::Synthesis)).map(ACell)?;
+            Ok((b, q, d))
+        })?;
+
+        let bits = BitsChip::construct(self.config.bits.clone());
+        for (name, cell) in [("a range", a), ("m range", m), ("b range", &b), ("q range", &q), ("d range", &d)] {
+            bits.decompose(layouter.namespace(|| name), cell, MOD_INVERSE_BITS)?;
+        }
+
+        let mul = MulChip::construct(self.config.mul.clone());
+        let ab = mul.mul(layouter.namespace(|| "a * b"), a, &b)?;
+        let qm = mul.mul(layouter.namespace(|| "q * m"), &q, m)?;
+
+        let linear = LinearChip::construct(self.config.linear.clone());
+        linear.linear_combination_eq(layouter.namespace(|| "a * b = q * m + 1"), &[ab, qm],
+            &[F::one(), -F::one()], F::one())?;
+        linear.linear_combination_eq(layouter.namespace(|| "b + d = m - 1"), &[b.clone(), d, m.clone()],
+            &[F::one(), F::one(), -F::one()], -F::one())?;
+
+        Ok(b)
+    }
+}
+
+// a^-1 mod m from the extended Euclidean algorithm, None unless gcd(a, m) = 1.
+// Also None for m < 2, the circuit can't satisfy b < m and a * b = q * m + 1 there
+pub fn mod_inverse_native(a: u64, m: u64) -> Option<u64> {
+    if m < 2 {
+        return None;
+    }
+    let (mut r0, mut r1) = (m as i128, (a % m) as i128);
+    let (mut t0, mut t1) = (0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, t0 - q * t1);
+    }
+    if r0 != 1 {
+        return None;
+    }
+    Some(t0.rem_euclid(m as i128) as u64)
+}
+
+// Public m, a and b (instance rows 0, 1 and 2), proves b = a^-1 mod m. The values all
+// come from the instance column, the chip derives q and d from them, so there is nothing
+// to withhold from keygen
+#[derive(Default, Clone)]
+pub struct ModInverseCircuit<F> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ModInverseCircuit<F> {
+    pub fn new() -> Self {
+        Self { _marker: PhantomData }
+    }
+
+    pub fn public(m: u64, a: u64, b: u64) -> Vec<Vec<F>> {
+        vec![vec![F::from(m), F::from(a), F::from(b)]]
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for ModInverseCircuit<F> {
+    type Config = ModInverseConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        ModInverseChip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let [col_a, col_m, _] = config.mul.advice;
+        let instance = config.instance;
+        let (a, m) = layouter.assign_region(|| "public", |mut region| {
+            let m = region.assign_advice_from_instance(|| "m", instance, 0, col_m, 0).map(ACell)?;
+            let a = region.assign_advice_from_instance(|| "a", instance, 1, col_a, 0).map(ACell)?;
+            Ok((a, m))
+        })?;
+
+        let b = ModInverseChip::construct(config).mod_inverse(layouter.namespace(|| "inverse"), &a, &m)?;
+        layouter.constrain_instance(b.0.cell(), instance, 2)
+    }
+}
+
+// Mock proves that b is the inverse of a mod m
+pub fn prove_mod_inverse_with(a: u64, m: u64, b: u64) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = ModInverseCircuit::<Fp>::new();
+    let public = ModInverseCircuit::public(m, a, b);
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+// Mock proves that a is invertible mod m, with its inverse public. Fails when
+// gcd(a, m) != 1, whatever b is claimed then
+pub fn prove_mod_inverse(a: u64, m: u64) -> Result<(), Vec<VerifyFailure>> {
+    prove_mod_inverse_with(a, m, mod_inverse_native(a, m).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_inverses() {
+        assert_eq!(mod_inverse_native(3, 7), Some(5));
+        prove_mod_inverse(3, 7).unwrap();
+        assert_eq!(mod_inverse_native(2, 11), Some(6));
+        prove_mod_inverse(2, 11).unwrap();
+        assert!(crate::dev::full_roundtrip(&ModInverseCircuit::new(), 9, vec![Fp::from(7), Fp::from(3), Fp::from(5)]));
+    }
+
+    #[test]
+    fn rejects_a_wrong_inverse() {
+        // 3 * 4 = 12 = 5 mod 7
+        let failures = prove_mod_inverse_with(3, 7, 4).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+
+    #[test]
+    fn rejects_a_noninvertible_value() {
+        // gcd(2, 4) = 2
+        assert_eq!(mod_inverse_native(2, 4), None);
+        assert!(prove_mod_inverse(2, 4).is_err());
+        for b in 0..4 {
+            assert!(prove_mod_inverse_with(2, 4, b).is_err());
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    dev::find_min_k,
+    fibonacci::{cell_value, fib_native, ACell, FiboChip, FiboConfig, FibonacciCircuit},
+};
+
+// Terms have to fit here, weights and the normalizer below 2^32
+pub const MOVING_AVG_TERM_BITS: usize = 64;
+// w0 * f(n) + w1 * f(n-1) + w2 * f(n-2) < 3 * 2^64 * 2^32, so the average fits in 98 bits
+pub const MOVING_AVG_BITS: usize = 98;
+
+// avg_n = floor((w0 * f(n) + w1 * f(n-1) + w2 * f(n-2)) / W), one row per n with
+// W * avg + rem = the weighted sum and rem + d = W - 1, both rem and d range checked so
+// rem < W. With the terms and avg range checked too the equation holds over the integers
+#[derive(Debug, Clone)]
+pub struct MovingAvgConfig {
+    pub terms: [Column<Advice>; 3], // f(n), f(n-1), f(n-2)
+    pub avg: [Column<Advice>; 3], // avg, rem, d
+    pub weights: [Column<Fixed>; 3],
+    pub norm: Column<Fixed>,
+    pub select: Selector,
+    pub bits: BitsConfig,
+}
+
+pub struct MovingAvgChip<F: FieldExt> {
+    config: MovingAvgConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MovingAvgChip<F> {
+    pub fn construct(config: MovingAvgConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MovingAvgConfig {
+        let terms = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let avg = [meta.advice_column(), meta.advice_column(), meta.advice_column()];
+        let weights = [meta.fixed_column(), meta.fixed_column(), meta.fixed_column()];
+        let norm = meta.fixed_column();
+        let select = meta.selector();
+
+        for column in terms.into_iter().chain(avg) {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("moving avg", |meta| {
+            // f(n) | f(n-1) | f(n-2) | avg | rem | d | w0 | w1 | w2 | W | selector
+            let s = meta.query_selector(select);
+            let [x0, x1, x2] = terms.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [avg, rem, d] = avg.map(|column| meta.query_advice(column, Rotation::cur()));
+            let [w0, w1, w2] = weights.map(|column| meta.query_fixed(column, Rotation::cur()));
+            let norm = meta.query_fixed(norm, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                s.clone() * (w0 * x0 + w1 * x1 + w2 * x2 - norm.clone() * avg - rem.clone()),
+                s * (rem + d + one - norm),
+            ]
+        });
+
+        MovingAvgConfig { terms, avg, weights, norm, select, bits: BitsChip::configure(meta) }
+    }
+
+    // One average per n >= 2 of terms f(0), f(1), .., in order
+    pub fn moving_avg(&self, mut layouter: impl Layouter<F>, terms: &[ACell<F>], weights: [u64; 3], w: u64) -> Result<Vec<ACell<F>>, Error> {
+        assert!(weights.iter().all(|&wi| wi < 1 << 32) && (1..1 << 32).contains(&w),
+            "weights and normalizer have to be below 2^32, the normalizer nonzero");
+        if terms.len() < 3 {
+            return Ok(vec![]);
+        }
+
+        let rows = layouter.assign_region(|| "moving avg", |mut region| {
+            let mut rows = vec![];
+            for (row, n) in (2..terms.len()).enumerate() {
+                self.config.select.enable(&mut region, row)?;
+                for (i, column) in self.config.terms.into_iter().enumerate() {
+                    terms[n - i].0.copy_advice(|| "term", &mut region, column, row)?;
+                }
+                for (column, wi) in self.config.weights.into_iter().zip(weights) {
+                    region.assign_fixed(|| "weight", column, row, || Ok(F::from(wi)))?;
+                }
+                region.assign_fixed(|| "W", self.config.norm, row, || Ok(F::from(w)))?;
+
+                let sum = (0..3).try_fold(0u128, |sum, i| {
+                    cell_value(&terms[n - i]).map(|x| sum + weights[i] as u128 * x.get_lower_128())
+                });
+                let [avg, rem, d] = [
+                    sum.map(|sum| sum / w as u128),
+                    sum.map(|sum| sum % w as u128),
+                    sum.map(|sum| w as u128 - 1 - sum % w as u128),
+                ];
+                let [col_avg, col_rem, col_d] = self.config.avg;
+                let avg = region.assign_advice(|| "avg", col_avg, row,
+                    || avg.map(F::from_u128).ok_or(Error::Synthesis)).map(ACell)?;
+                let rem = region.assign_advice(|| "rem", col_rem, row,
+                    || rem.map(F::from_u128).ok_or(Error::Synthesis)).map(ACell)?;
+                let d = region.assign_advice(|| "d", col_d, row,
+                    || d.map(F::from_u128).ok_or(Error::Synthesis)).map(ACell)?;
+                rows.push((avg, rem, d));
+            }
+            Ok(rows)
+        })?;
+
+        let bits = BitsChip::construct(self.config.bits.clone());
+        // rem < W <= 2^rem_bits
+        let rem_bits = (64 - (w - 1).leading_zeros() as usize).max(1);
+        for term in terms {
+            bits.decompose(layouter.namespace(|| "term range"), term, MOVING_AVG_TERM_BITS)?;
+        }
+        for (avg, rem, d) in &rows {
+            bits.decompose(layouter.namespace(|| "avg range"), avg, MOVING_AVG_BITS)?;
+            bits.decompose(layouter.namespace(|| "rem range"), rem, rem_bits)?;
+            bits.decompose(layouter.namespace(|| "d range"), d, rem_bits)?;
+        }
+
+        Ok(rows.into_iter().map(|(avg, _, _)| avg).collect())
+    }
+}
+
+// The same averages outside the circuit
+pub fn moving_avg_native(terms: &[u64], weights: [u64; 3], w: u64) -> Vec<u64> {
+    (2..terms.len())
+        .map(|n| {
+            let sum: u128 = (0..3).map(|i| weights[i] as u128 * terms[n - i] as u128).sum();
+            (sum / w as u128) as u64
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct MovingAvgCircuitConfig {
+    pub fibo: FiboConfig,
+    pub avg: MovingAvgConfig,
+}
+
+// The moving averages of f(0)..f(steps) for public seeds, exposed after the seeds
+#[derive(Default, Clone)]
+pub struct MovingAvgCircuit<F> {
+    pub fibo: FibonacciCircuit<F>,
+    pub weights: [u64; 3],
+    pub w: u64,
+}
+
+impl<F: FieldExt> Circuit<F> for MovingAvgCircuit<F> {
+    type Config = MovingAvgCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { fibo: self.fibo.without_witnesses(), ..self.clone() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        MovingAvgCircuitConfig {
+            fibo: FibonacciCircuit::configure(meta),
+            avg: MovingAvgChip::configure(meta),
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config.fibo);
+        let terms = self.fibo.compute_all(&chip, layouter.namespace(|| "trace"))?;
+
+        let averages = MovingAvgChip::construct(config.avg)
+            .moving_avg(layouter.namespace(|| "moving avg"), &terms, self.weights, self.w)?;
+        for (i, avg) in averages.iter().enumerate() {
+            chip.expose_public(layouter.namespace(|| "avg"), avg, 2 + i)?;
+        }
+        Ok(())
+    }
+}
+
+// Mock proves the moving averages of f(0)..f(steps) from seeds 1, 1, steps at least 2
+pub fn prove_moving_avg(steps: usize, weights: [u64; 3], w: u64) -> Result<(), Vec<VerifyFailure>> {
+    assert!(steps >= 2, "the first average is at f(2)");
+    let terms: Vec<u64> = (0..=steps).map(|n| fib_native(Fp::one(), Fp::one(), n).get_lower_128() as u64).collect();
+    let mut public = vec![Fp::one(), Fp::one()];
+    public.extend(moving_avg_native(&terms, weights, w).into_iter().map(Fp::from));
+
+    let circuit = MovingAvgCircuit { fibo: FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), steps), weights, w };
+    let public = vec![public];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WEIGHTS: [u64; 3] = [1, 2, 1];
+
+    fn terms(steps: usize) -> Vec<u64> {
+        (0..=steps).map(|n| fib_native(Fp::one(), Fp::one(), n).get_lower_128() as u64).collect()
+    }
+
+    #[test]
+    fn matches_the_float_average() {
+        let terms = terms(12);
+        let averages = moving_avg_native(&terms, WEIGHTS, 4);
+        assert_eq!(averages.len(), 11);
+        for (n, &avg) in (2..).zip(&averages) {
+            let exact = (terms[n] + 2 * terms[n - 1] + terms[n - 2]) as f64 / 4.0;
+            // truncated, so below the exact average by less than 1
+            assert!(avg as f64 <= exact && exact - (avg as f64) < 1.0, "avg {} of f({}) vs {}", avg, n, exact);
+        }
+        prove_moving_avg(12, WEIGHTS, 4).unwrap();
+    }
+
+    #[test]
+    fn rejects_a_wrong_average() {
+        let circuit = MovingAvgCircuit { fibo: FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 12), weights: WEIGHTS, w: 4 };
+        let mut public = vec![Fp::one(), Fp::one()];
+        public.extend(moving_avg_native(&terms(12), WEIGHTS, 4).into_iter().map(Fp::from));
+        let k = find_min_k(|| (circuit.clone(), vec![public.clone()]));
+        crate::assert_circuit!(circuit, k, vec![public.clone()]);
+
+        // (8 + 2 * 5 + 3) / 4 = 5.25, claimed as 6
+        public[5] += Fp::one();
+        crate::assert_circuit_fails!(circuit, k, vec![public], VerifyFailure::Permutation { .. });
+    }
+}
@@ -0,0 +1,76 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    fibonacci::{cell_value, ACell},
+    pool::ColumnPool,
+};
+
+// a * b = c, the multiplicative twin of the Fibonacci add gate
+#[derive(Debug, Clone)]
+pub struct MulConfig {
+    pub advice: [Column<Advice>; 3],
+    pub select: Selector,
+    // for constant operands
+    pub constant: Column<Fixed>,
+}
+
+pub struct MulChip<F: FieldExt> {
+    config: MulConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> MulChip<F> {
+    pub fn construct(config: MulConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> MulConfig {
+        let pool = ColumnPool::new(meta, 3);
+        Self::configure_with_columns(meta, &pool)
+    }
+
+    pub fn configure_with_columns(meta: &mut ConstraintSystem<F>, pool: &ColumnPool) -> MulConfig {
+        let advice: [Column<Advice>; 3] = pool.advice();
+        let selector = meta.selector();
+        let constant = meta.fixed_column();
+
+        meta.enable_constant(constant);
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("mul", |meta| {
+            // col_a | col_b | col_c | selector
+            //   a      b       c        s
+            let s = meta.query_selector(selector);
+            let [a, b, c] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            vec![s * (a * b - c)]
+        });
+
+        MulConfig { advice, select: selector, constant }
+    }
+
+    pub fn mul(&self, mut layouter: impl Layouter<F>, a: &ACell<F>, b: &ACell<F>) -> Result<ACell<F>, Error> {
+        layouter.assign_region(|| "mul", |mut region| {
+            self.config.select.enable(&mut region, 0)?;
+
+            a.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+            b.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+
+            let c_val = cell_value(a).and_then(|a| cell_value(b).map(|b| a * b));
+            region.assign_advice(|| "c", self.config.advice[2], 0,
+                || c_val.ok_or(Error::Synthesis)).map(ACell)
+        })
+    }
+
+    // a * a, both operands are copies of the same cell
+    pub fn square(&self, layouter: impl Layouter<F>, a: &ACell<F>) -> Result<ACell<F>, Error> {
+        self.mul(layouter, a, a)
+    }
+}
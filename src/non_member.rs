@@ -0,0 +1,150 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    dev::find_min_k,
+    fibonacci::{cell_value, ACell},
+    layouter_ext::LayouterExt,
+};
+
+// x is none of s1..sn, by witnessing the inverse of every x - si. One row per set element,
+// with a copy of x, the element as a fixed value and the inverse. A zero difference has
+// no inverse, so an x in the set can't satisfy its row
+#[derive(Debug, Clone)]
+pub struct NonMembershipConfig {
+    pub x: Column<Advice>,
+    pub inv: Column<Advice>,
+    pub element: Column<Fixed>,
+    pub select: Selector,
+}
+
+pub struct NonMembershipChip<F: FieldExt> {
+    config: NonMembershipConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> NonMembershipChip<F> {
+    pub fn construct(config: NonMembershipConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> NonMembershipConfig {
+        let x = meta.advice_column();
+        let inv = meta.advice_column();
+        let element = meta.fixed_column();
+        let select = meta.selector();
+
+        meta.enable_equality(x);
+
+        meta.create_gate("not member", |meta| {
+            // x | inv          | element | selector
+            // x | 1 / (x - si) |   si    |    s
+            let s = meta.query_selector(select);
+            let x = meta.query_advice(x, Rotation::cur());
+            let inv = meta.query_advice(inv, Rotation::cur());
+            let si = meta.query_fixed(element, Rotation::cur());
+            vec![s * ((x - si) * inv - Expression::Constant(F::one()))]
+        });
+
+        NonMembershipConfig { x, inv, element, select }
+    }
+
+    // Nothing is in the empty set, so it assigns nothing
+    pub fn assert_not_member(&self, mut layouter: impl Layouter<F>, x: &ACell<F>, set: &[F]) -> Result<(), Error> {
+        if set.is_empty() {
+            return Ok(());
+        }
+
+        layouter.assign_region(|| "not member", |mut region| {
+            for (row, si) in set.iter().enumerate() {
+                self.config.select.enable(&mut region, row)?;
+                x.0.copy_advice(|| "x", &mut region, self.config.x, row)?;
+                region.assign_fixed(|| "element", self.config.element, row, || Ok(*si))?;
+
+                // x = si has no inverse, 0 is assigned and the row fails
+                let inv = cell_value(x).map(|x| (x - si).invert().unwrap_or(F::zero()));
+                region.assign_advice(|| "inv", self.config.inv, row, || inv.ok_or(Error::Synthesis))?;
+            }
+            Ok(())
+        })
+    }
+}
+
+// Public x (instance row 0) that isn't in a fixed set. The set is part of the shape, x
+// comes from the instance column, so there is no witness to withhold from keygen
+#[derive(Default, Clone)]
+pub struct NonMembershipCircuit<F> {
+    pub set: Vec<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> NonMembershipCircuit<F> {
+    pub fn new(set: Vec<u64>) -> Self {
+        Self { set, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for NonMembershipCircuit<F> {
+    type Config = (NonMembershipConfig, Column<Instance>);
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        (NonMembershipChip::configure(meta), instance)
+    }
+
+    fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let x = layouter.assign_from_instance("x", instance, 0, config.x)?;
+
+        let set: Vec<F> = self.set.iter().map(|&s| F::from(s)).collect();
+        NonMembershipChip::construct(config).assert_not_member(layouter.namespace(|| "not member"), &x, &set)
+    }
+}
+
+// Mock proves that x is not in the set, the failures point at the rows of the elements equal to x
+pub fn prove_not_member(x: u64, set: &[u64]) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = NonMembershipCircuit::<Fp>::new(set.to_vec());
+    let public = vec![vec![Fp::from(x)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn four_is_not_in_the_set() {
+        prove_not_member(4, &[1, 3, 5]).unwrap();
+        assert!(crate::dev::full_roundtrip(&NonMembershipCircuit::new(vec![1, 3, 5]), 4, vec![Fp::from(4)]));
+    }
+
+    #[test]
+    fn three_is() {
+        let failures = prove_not_member(3, &[1, 3, 5]).unwrap_err();
+        // only the row of 3 fails
+        assert_eq!(failures.len(), 1);
+        assert!(matches!(failures[0], VerifyFailure::ConstraintNotSatisfied { .. }));
+        assert!(failures[0].to_string().contains("at offset 1"), "{}", failures[0]);
+    }
+
+    #[test]
+    fn nothing_is_in_the_empty_set() {
+        for x in [0, 3, 4] {
+            prove_not_member(x, &[]).unwrap();
+        }
+    }
+}
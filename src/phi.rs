@@ -0,0 +1,211 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    fibonacci::{cell_value, ACell},
+};
+
+// x has to fit here, so x * phi can't wrap around the modulus
+pub const PHI_INPUT_BITS: usize = 64;
+// largest scale is 2^PHI_MAX_SCALE_BITS, keeps x * phi below 2^126
+pub const PHI_MAX_SCALE_BITS: u32 = 60;
+
+// out = floor(x * phi / scale) where phi = floor(scale * 1.618..) is a fixed value.
+// The remainder of the division is range checked below scale, and x and out are range
+// checked too so the equation holds over the integers. Both roundings are down, so
+// phi * x - x / scale - 1 < out <= phi * x for the real golden ratio
+#[derive(Debug, Clone)]
+pub struct PhiConfig {
+    pub advice: [Column<Advice>; 3], // x, out, rem
+    pub phi: Column<Fixed>,
+    pub scale: Column<Fixed>,
+    pub select: Selector,
+    pub bits: BitsConfig,
+}
+
+pub struct PhiChip<F: FieldExt> {
+    config: PhiConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> PhiChip<F> {
+    pub fn construct(config: PhiConfig) -> Self {
+        Self { config, _marker: std::marker::PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PhiConfig {
+        let col_x = meta.advice_column();
+        let col_out = meta.advice_column();
+        let col_rem = meta.advice_column();
+        let phi = meta.fixed_column();
+        let scale = meta.fixed_column();
+        let select = meta.selector();
+
+        meta.enable_equality(col_x);
+        meta.enable_equality(col_out);
+        meta.enable_equality(col_rem);
+
+        meta.create_gate("mul phi", |meta| {
+            // col_x | col_out | col_rem | phi | scale | selector
+            //   x      out       rem      phi   scale     s
+            let s = meta.query_selector(select);
+            let x = meta.query_advice(col_x, Rotation::cur());
+            let out = meta.query_advice(col_out, Rotation::cur());
+            let rem = meta.query_advice(col_rem, Rotation::cur());
+            let phi = meta.query_fixed(phi, Rotation::cur());
+            let scale = meta.query_fixed(scale, Rotation::cur());
+            vec![s * (x * phi - out * scale - rem)]
+        });
+
+        PhiConfig {
+            advice: [col_x, col_out, col_rem],
+            phi,
+            scale,
+            select,
+            bits: BitsChip::configure(meta),
+        }
+    }
+
+    // scale is a power of two, 2..=2^PHI_MAX_SCALE_BITS, and sets the precision
+    pub fn mul_phi(&self, mut layouter: impl Layouter<F>, x: &ACell<F>, scale: u64) -> Result<ACell<F>, Error> {
+        assert!(scale.is_power_of_two() && scale >= 2, "scale has to be a power of two");
+        let scale_bits = scale.trailing_zeros();
+        assert!(scale_bits <= PHI_MAX_SCALE_BITS, "scale above 2^{}", PHI_MAX_SCALE_BITS);
+
+        let phi = phi_fixed(scale);
+        let (out, rem) = layouter.assign_region(|| "mul phi", |mut region| {
+            self.config.select.enable(&mut region, 0)?;
+
+            x.0.copy_advice(|| "x", &mut region, self.config.advice[0], 0)?;
+            region.assign_fixed(|| "phi", self.config.phi, 0, || Ok(F::from_u128(phi)))?;
+            region.assign_fixed(|| "scale", self.config.scale, 0, || Ok(F::from(scale)))?;
+
+            // an x the range check below rejects can overflow, that leaves out and rem
+            // without a witness instead of panicking
+            let product = cell_value(x).and_then(|x| x.get_lower_128().checked_mul(phi));
+            let out = region.assign_advice(|| "out", self.config.advice[1], 0,
+                || product.map(|p| F::from_u128(p >> scale_bits)).ok_or(Error::Synthesis)).map(ACell)?;
+            let rem = region.assign_advice(|| "rem", self.config.advice[2], 0,
+                || product.map(|p| F::from_u128(p % scale as u128)).ok_or(Error::Synthesis)).map(ACell)?;
+            Ok((out, rem))
+        })?;
+
+        let bits = BitsChip::construct(self.config.bits.clone());
+        bits.decompose(layouter.namespace(|| "x range"), x, PHI_INPUT_BITS)?;
+        // out <= phi * x < 2^65
+        bits.decompose(layouter.namespace(|| "out range"), &out, PHI_INPUT_BITS + 1)?;
+        bits.decompose(layouter.namespace(|| "rem range"), &rem, scale_bits as usize)?;
+
+        Ok(out)
+    }
+}
+
+// floor(scale * phi) = floor((scale + sqrt(5 * scale^2)) / 2), exact in integers
+pub fn phi_fixed(scale: u64) -> u128 {
+    let scale = scale as u128;
+    (scale + isqrt(5 * scale * scale)) / 2
+}
+
+fn isqrt(n: u128) -> u128 {
+    // the float estimate is off by a few at most, fix it up
+    let mut x = (n as f64).sqrt() as u128;
+    while x * x > n {
+        x -= 1;
+    }
+    while (x + 1) * (x + 1) <= n {
+        x += 1;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::{MockProver, VerifyFailure}, pasta::Fp};
+
+    use super::*;
+    use crate::{
+        fibonacci::{fib_native, FiboChip, FiboConfig, FibonacciCircuit},
+        layouter_ext::LayouterExt,
+    };
+
+    const N: usize = 60;
+    const SCALE: u64 = 1 << 32;
+
+    // mul_phi(f(N)) over the trace from the public seeds 0, 1, its output at instance row 2
+    #[derive(Clone)]
+    struct PhiCircuit;
+
+    impl Circuit<Fp> for PhiCircuit {
+        type Config = (FiboConfig, PhiConfig);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            (FiboChip::configure(meta, instance), PhiChip::configure(meta))
+        }
+
+        fn synthesize(&self, (fibo, config): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let trace = FibonacciCircuit::new(Some(Fp::zero()), Some(Fp::one()), N);
+            let cells = trace.compute_all(&FiboChip::construct(fibo.clone()), layouter.namespace(|| "trace"))?;
+            let out = PhiChip::construct(config).mul_phi(layouter.namespace(|| "phi f(n)"), &cells[N], SCALE)?;
+            layouter.constrain_instance(out.0.cell(), fibo.instance, 2)
+        }
+    }
+
+    // mul_phi of a free witness x at the largest scale
+    #[derive(Clone)]
+    struct PhiOf(Fp);
+
+    impl Circuit<Fp> for PhiOf {
+        type Config = (FiboConfig, PhiConfig);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            PhiCircuit::configure(meta)
+        }
+
+        fn synthesize(&self, (fibo, config): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let x = layouter.assign_value("x", fibo.advice[0], Some(self.0))?;
+            PhiChip::construct(config).mul_phi(layouter.namespace(|| "phi x"), &x, 1 << PHI_MAX_SCALE_BITS)?;
+            Ok(())
+        }
+    }
+
+    fn fib(n: usize) -> u128 {
+        fib_native(Fp::zero(), Fp::one(), n).get_lower_128()
+    }
+
+    #[test]
+    fn approximates_the_next_term() {
+        let out = fib(N) * phi_fixed(SCALE) / SCALE as u128;
+        // phi * x - x / scale - 1 < out <= phi * x, and f(n + 1) is within 1 of phi * f(n)
+        let bound = fib(N) / SCALE as u128 + 2;
+        assert!(out.abs_diff(fib(N + 1)) <= bound, "{} is not within {} of {}", out, bound, fib(N + 1));
+
+        let public = |out: u128| vec![vec![Fp::zero(), Fp::one(), Fp::from_u128(out)]];
+        crate::assert_circuit!(PhiCircuit, 9, public(out));
+        crate::assert_circuit_fails!(PhiCircuit, 9, public(out + 1), VerifyFailure::Permutation { .. });
+    }
+
+    #[test]
+    fn oversized_input_fails_synthesis() {
+        // phi * 2^100 doesn't fit in a u128
+        let error = MockProver::run(9, &PhiOf(Fp::from_u128(1 << 100)), vec![vec![]]).err();
+        assert!(matches!(error, Some(Error::Synthesis)), "{:?}", error);
+        crate::assert_circuit!(PhiOf(Fp::from(u64::MAX)), 9, vec![vec![]]);
+    }
+}
@@ -0,0 +1,103 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    plonk::*
+};
+
+// Advice columns allocated once and handed out to several chips' configure, so chips
+// that sit side by side share columns instead of each adding their own.
+// Equality is enabled on all of them, since most chips copy in and out of their columns
+#[derive(Debug, Clone)]
+pub struct ColumnPool {
+    advice: Vec<Column<Advice>>,
+}
+
+impl ColumnPool {
+    pub fn new<F: FieldExt>(meta: &mut ConstraintSystem<F>, size: usize) -> Self {
+        let advice = (0..size)
+            .map(|_| {
+                let column = meta.advice_column();
+                meta.enable_equality(column);
+                column
+            })
+            .collect();
+        Self { advice }
+    }
+
+    pub fn len(&self) -> usize {
+        self.advice.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.advice.is_empty()
+    }
+
+    // The first N columns of the pool
+    pub fn advice<const N: usize>(&self) -> [Column<Advice>; N] {
+        assert!(N <= self.advice.len(), "pool has {} columns, chip needs {}", self.advice.len(), N);
+        std::array::from_fn(|i| self.advice[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{
+        circuit::{Layouter, SimpleFloorPlanner},
+        pasta::{EqAffine, Fp},
+        poly::commitment::Params,
+    };
+
+    use super::*;
+    use crate::{arith::ArithChip, fibonacci::FiboChip, inspect::advice_columns, mul::MulChip};
+
+    // An add chip (FIBO = false: ArithChip, FIBO = true: FiboChip) next to a mul chip, on
+    // one pool or on columns of their own. Only the layout matters, nothing is assigned
+    #[derive(Default)]
+    struct Pair<const SHARED: bool, const FIBO: bool>;
+
+    impl<const SHARED: bool, const FIBO: bool> Circuit<Fp> for Pair<SHARED, FIBO> {
+        type Config = ();
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            let size = if FIBO { crate::fibonacci::FIBO_COLUMNS } else { 3 };
+            let pool = ColumnPool::new(meta, size);
+            if FIBO {
+                FiboChip::configure_with_columns(meta, instance, &pool);
+            } else {
+                ArithChip::configure_with_columns(meta, &pool, true);
+            }
+            if SHARED {
+                MulChip::configure_with_columns(meta, &pool);
+            } else {
+                MulChip::configure(meta);
+            }
+        }
+
+        fn synthesize(&self, _: Self::Config, _: impl Layouter<Fp>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn advice_of<C: Circuit<Fp> + Default>() -> usize {
+        let params = Params::<EqAffine>::new(4);
+        advice_columns(&keygen_vk(&params, &C::default()).expect("keygen failed"))
+    }
+
+    #[test]
+    fn shared_pool_adds_no_columns() {
+        assert_eq!(advice_of::<Pair<true, false>>(), 3);
+        assert_eq!(advice_of::<Pair<false, false>>(), 6);
+    }
+
+    #[test]
+    fn fibonacci_shares_its_pool() {
+        assert_eq!(advice_of::<Pair<true, true>>(), 7);
+        assert_eq!(advice_of::<Pair<false, true>>(), 10);
+    }
+}
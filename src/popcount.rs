@@ -0,0 +1,170 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    dev::find_min_k,
+    fibonacci::{cell_value, ACell},
+    layouter_ext::LayouterExt,
+    linear::{LinearChip, LinearConfig},
+};
+
+// Number of set bits of a value. BitsChip decomposes it (which also range checks it to the
+// width), and the count is a witness pinned to the sum of the bits by a linear combination
+// bits - count = 0. The bits are boolean, so the sum is the count over the integers too
+#[derive(Debug, Clone)]
+pub struct PopcountConfig {
+    pub bits: BitsConfig,
+    pub linear: LinearConfig,
+}
+
+pub struct PopcountChip<F: FieldExt> {
+    config: PopcountConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> PopcountChip<F> {
+    pub fn construct(config: PopcountConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> PopcountConfig {
+        PopcountConfig {
+            bits: BitsChip::configure(meta),
+            linear: LinearChip::configure(meta),
+        }
+    }
+
+    // value has to fit in width bits, 1..=128
+    pub fn popcount(&self, mut layouter: impl Layouter<F>, value: &ACell<F>, width: usize) -> Result<ACell<F>, Error> {
+        let bits = BitsChip::construct(self.config.bits.clone())
+            .decompose(layouter.namespace(|| "bits"), value, width)?;
+
+        let count = cell_value(value).map(|v| F::from(v.get_lower_128().count_ones() as u64));
+        let count = layouter.assign_value("popcount", self.config.linear.value, count)?;
+
+        let mut terms = bits;
+        terms.push(count.clone());
+        let mut coeffs = vec![F::one(); width];
+        coeffs.push(-F::one());
+        LinearChip::construct(self.config.linear.clone())
+            .linear_combination_eq(layouter.namespace(|| "sum of bits"), &terms, &coeffs, F::zero())?;
+
+        Ok(count)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PopcountCircuitConfig {
+    pub popcount: PopcountConfig,
+    pub instance: Column<Instance>,
+}
+
+// Private value, public popcount at instance row 0
+#[derive(Default, Clone)]
+pub struct PopcountCircuit<F> {
+    pub value: Option<u64>,
+    pub width: usize,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> PopcountCircuit<F> {
+    pub fn new(value: u64, width: usize) -> Self {
+        Self { value: Some(value), width, _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for PopcountCircuit<F> {
+    type Config = PopcountCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { value: None, ..self.clone() }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        PopcountCircuitConfig { popcount: PopcountChip::configure(meta), instance }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let value = layouter.assign_value("value", config.popcount.linear.value, self.value.map(F::from))?;
+        let count = PopcountChip::construct(config.popcount)
+            .popcount(layouter.namespace(|| "popcount"), &value, self.width)?;
+        layouter.constrain_instance(count.0.cell(), config.instance, 0)
+    }
+}
+
+// Mock proves that value has `count` set bits, with value decomposed to width bits
+pub fn prove_popcount(value: u64, width: usize, count: u64) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = PopcountCircuit::<Fp>::new(value, width);
+    let public = vec![vec![Fp::from(count)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_set_bits_at_8_bits() {
+        for (value, count) in [(13, 3), (0, 0), (255, 8)] {
+            assert_eq!(prove_popcount(value, 8, count), Ok(()), "popcount({})", value);
+        }
+    }
+
+    #[test]
+    fn rejects_a_value_wider_than_the_bits() {
+        // the 8 lowest bits of 256 are all zero and don't add back up to it
+        assert!(prove_popcount(256, 8, 0).is_err());
+    }
+
+    // 13 decomposed as usual, but a count of 4 next to its bits
+    #[derive(Clone)]
+    struct ForgedCount;
+
+    impl Circuit<Fp> for ForgedCount {
+        type Config = PopcountCircuitConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            PopcountCircuit::<Fp>::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let value = layouter.assign_value("value", config.popcount.linear.value, Some(Fp::from(13)))?;
+            let mut terms = BitsChip::construct(config.popcount.bits)
+                .decompose(layouter.namespace(|| "bits"), &value, 8)?;
+            let count = layouter.assign_value("popcount", config.popcount.linear.value, Some(Fp::from(4)))?;
+            layouter.constrain_instance(count.0.cell(), config.instance, 0)?;
+
+            terms.push(count);
+            let mut coeffs = vec![Fp::one(); 8];
+            coeffs.push(-Fp::one());
+            LinearChip::construct(config.popcount.linear)
+                .linear_combination_eq(layouter.namespace(|| "sum of bits"), &terms, &coeffs, Fp::zero())
+        }
+    }
+
+    #[test]
+    fn rejects_a_count_off_the_sum_of_bits() {
+        // the running sum ends on 3 - 4 instead of the constant 0
+        crate::assert_circuit_fails!(ForgedCount, 8, vec![vec![Fp::from(4)]],
+            VerifyFailure::Permutation { .. });
+    }
+}
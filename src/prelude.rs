@@ -0,0 +1,23 @@
+//! Everything needed to build and prove the Fibonacci circuit:
+//!
+//! ```
+//! use halo2_circuits::prelude::*;
+//!
+//! let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+//! let public = vec![Fp::one(), Fp::one(), Fp::from(55)];
+//! let prover = MockProver::run(circuit.min_k(), &circuit, vec![public]).unwrap();
+//! assert_eq!(prover.verify(), Ok(()));
+//! ```
+pub use crate::{
+    assert_circuit, assert_circuit_fails,
+    error::FiboError,
+    fibonacci::{cell_value, ACell, FiboChip, FiboConfig, FibonacciCircuit},
+    prover::{keygen, prove, verify, ProofRng},
+};
+
+pub use halo2_proofs::{
+    arithmetic::FieldExt,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::Error,
+};
@@ -0,0 +1,231 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    is_zero::{IsZeroChip, IsZeroConfig},
+    range_table::RangeTableConfig,
+};
+
+// n, the quotients and the distance of each remainder to its divisor all have to fit here
+pub const PRIME_BITS: usize = 8;
+
+// Proves a public n is prime by trial division: for every d up to sqrt(n) we witness
+// n = q*d + r with r != 0. The range checks on q, r - 1 and d - 1 - r keep the division
+// honest, without them any r works since q can be any field element. r - 1 also matters on
+// its own: with only d - 1 - r checked a negative r gets through, e.g. 15 = 6*3 + (-3).
+#[derive(Debug, Clone)]
+pub struct PrimeConfig<F: FieldExt> {
+    pub advice: [Column<Advice>; 3], // n, q, r
+    pub divisor: Column<Fixed>,
+    pub is_zero: IsZeroConfig<F>,
+    pub range: RangeTableConfig<F, PRIME_BITS>,
+    pub select: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct PrimeChip<F: FieldExt> {
+    config: PrimeConfig<F>,
+}
+
+impl<F: FieldExt> PrimeChip<F> {
+    pub fn construct(config: PrimeConfig<F>) -> Self {
+        Self { config }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> PrimeConfig<F> {
+        let col_n = meta.advice_column();
+        let col_q = meta.advice_column();
+        let col_r = meta.advice_column();
+        let col_r_inv = meta.advice_column();
+        let divisor = meta.fixed_column();
+        // lookups can't use simple selectors
+        let selector = meta.complex_selector();
+        let range = RangeTableConfig::configure(meta);
+
+        meta.enable_equality(col_n);
+        meta.enable_equality(instance);
+
+        let is_zero = IsZeroChip::configure(
+            meta,
+            |meta| meta.query_selector(selector),
+            |meta| meta.query_advice(col_r, Rotation::cur()),
+            col_r_inv,
+        );
+
+        meta.create_gate("division", |meta| {
+            // col_n | col_q | col_r | divisor | selector
+            //   n      q       r        d          s
+            let s = meta.query_selector(selector);
+            let n = meta.query_advice(col_n, Rotation::cur());
+            let q = meta.query_advice(col_q, Rotation::cur());
+            let r = meta.query_advice(col_r, Rotation::cur());
+            let d = meta.query_fixed(divisor, Rotation::cur());
+            vec![s * (n - q * d - r)]
+        });
+
+        meta.create_gate("nonzero remainder", |meta| {
+            let s = meta.query_selector(selector);
+            vec![s * is_zero.expr()]
+        });
+
+        // 0 <= q < 2^BITS
+        meta.lookup(|meta| {
+            let s = meta.query_selector(selector);
+            let q = meta.query_advice(col_q, Rotation::cur());
+            vec![(s * q, range.value)]
+        });
+
+        // 0 < r, which is the nonzero check again but also keeps r from being negative
+        meta.lookup(|meta| {
+            let s = meta.query_selector(selector);
+            let r = meta.query_advice(col_r, Rotation::cur());
+            vec![(s * (r - Expression::Constant(F::one())), range.value)]
+        });
+
+        // r < d
+        meta.lookup(|meta| {
+            let s = meta.query_selector(selector);
+            let r = meta.query_advice(col_r, Rotation::cur());
+            let d = meta.query_fixed(divisor, Rotation::cur());
+            vec![(s * (d - Expression::Constant(F::one()) - r), range.value)]
+        });
+
+        PrimeConfig {
+            advice: [col_n, col_q, col_r],
+            divisor,
+            is_zero,
+            range,
+            select: selector,
+            instance,
+        }
+    }
+
+    // One row per candidate divisor, n comes straight from the instance column
+    pub fn assign_divisor(&self, layouter: impl Layouter<F>, n: u64, d: u64) -> Result<(), Error> {
+        self.assign_division(layouter, d, F::from(n / d), F::from(n % d))
+    }
+
+    // The same row for any quotient and remainder, honest or not
+    pub fn assign_division(&self, mut layouter: impl Layouter<F>, d: u64, q: F, r: F) -> Result<(), Error> {
+        layouter.assign_region(|| format!("divisor {}", d), |mut region| {
+            self.config.select.enable(&mut region, 0)?;
+
+            region.assign_advice_from_instance(|| "n", self.config.instance, 0, self.config.advice[0], 0)?;
+            region.assign_fixed(|| "d", self.config.divisor, 0, || Ok(F::from(d)))?;
+
+            region.assign_advice(|| "q", self.config.advice[1], 0, || Ok(q))?;
+            region.assign_advice(|| "r", self.config.advice[2], 0, || Ok(r))?;
+
+            IsZeroChip::construct(self.config.is_zero.clone()).assign(&mut region, 0, Some(r))
+        })
+    }
+}
+
+// The divisors depend on n, so n is part of the circuit shape as well as the public input.
+// Nothing here is secret, the quotients and remainders are recomputed from n
+#[derive(Default, Clone)]
+pub struct PrimeCircuit<F> {
+    pub n: u64,
+    // (d, q, r) witnessed for divisor d instead, see `forged`
+    pub forged: Option<(u64, F, F)>,
+}
+
+impl<F: FieldExt> PrimeCircuit<F> {
+    pub fn new(n: u64) -> Self {
+        Self { n, forged: None }
+    }
+
+    // Any quotient and remainder for divisor d, for checking the range checks reject them
+    pub fn forged(n: u64, d: u64, q: F, r: F) -> Self {
+        Self { forged: Some((d, q, r)), ..Self::new(n) }
+    }
+
+    // candidate divisors 2..=isqrt(n)
+    pub fn divisors(&self) -> impl Iterator<Item = u64> {
+        let n = self.n;
+        (2..).take_while(move |d| d * d <= n)
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for PrimeCircuit<F> {
+    type Config = PrimeConfig<F>;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new(self.n)
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        PrimeChip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        config.range.load(&mut layouter)?;
+        let chip = PrimeChip::construct(config);
+
+        for d in self.divisors() {
+            match self.forged {
+                Some((forged_d, q, r)) if forged_d == d => chip.assign_division(layouter.namespace(|| "trial division"), d, q, r)?,
+                _ => chip.assign_divisor(layouter.namespace(|| "trial division"), self.n, d)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Mock proves that n is prime, the failures point at the divisor rows that divide n
+pub fn prove_prime(n: u64) -> Result<(), Vec<VerifyFailure>> {
+    prove_prime_with(PrimeCircuit::new(n))
+}
+
+pub fn prove_prime_with(circuit: PrimeCircuit<Fp>) -> Result<(), Vec<VerifyFailure>> {
+    let n = circuit.n;
+    assert!((2..1 << PRIME_BITS).contains(&n), "n has to be in 2..2^{}", PRIME_BITS);
+
+    // the range table needs 2^BITS rows plus the blinding rows
+    let k = PRIME_BITS as u32 + 1;
+    let prover = MockProver::run(k, &circuit, vec![vec![Fp::from(n)]])
+        .expect("k is large enough for the range table");
+    prover.verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_primes() {
+        for n in [2, 3, 13, 97, 251] {
+            assert_eq!(prove_prime(n), Ok(()), "{} is prime", n);
+        }
+    }
+
+    #[test]
+    fn rejects_15_at_divisor_3() {
+        let failures = prove_prime(15).unwrap_err();
+        assert!(!failures.is_empty());
+        for failure in failures {
+            assert!(failure.to_string().contains("'divisor 3'"), "unexpected failure: {}", failure);
+        }
+    }
+
+    #[test]
+    fn rejects_a_negative_remainder() {
+        // 15 = 6*3 + (-3): the division holds, d - 1 - r = 5 and q = 6 are in range, and
+        // r isn't zero. Only the r - 1 lookup is left to catch it
+        let circuit = PrimeCircuit::forged(15, 3, Fp::from(6), -Fp::from(3));
+        let failures = prove_prime_with(circuit).unwrap_err();
+        assert!(!failures.is_empty());
+        for failure in failures {
+            assert!(matches!(failure, VerifyFailure::Lookup { .. }), "unexpected failure: {}", failure);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::{cell::RefCell, time::{Duration, Instant}};
+
+use halo2_proofs::{
+    arithmetic::Field,
+    circuit::*,
+    plonk::*
+};
+
+// Where synthesis spends its time, per region. A circuit wraps its layouter in a
+// ProfiledLayouter (FibonacciCircuit does when the profile feature is on) and every
+// assign_region through it, namespaced or not, lands in a thread local list.
+// The time includes both passes the floor planner makes over a region
+#[derive(Debug, Clone)]
+pub struct RegionTiming {
+    pub name: String,
+    pub elapsed: Duration,
+}
+
+thread_local! {
+    static PROFILE: RefCell<Vec<RegionTiming>> = const { RefCell::new(vec![]) };
+}
+
+// Everything recorded on this thread so far, in order, and clears it
+pub fn take_region_profile() -> Vec<RegionTiming> {
+    PROFILE.with(|profile| std::mem::take(&mut *profile.borrow_mut()))
+}
+
+pub struct ProfiledLayouter<L> {
+    inner: L,
+}
+
+impl<L> ProfiledLayouter<L> {
+    pub fn new(inner: L) -> Self {
+        Self { inner }
+    }
+}
+
+impl<F: Field, L: Layouter<F>> Layouter<F> for ProfiledLayouter<L> {
+    // its own root, so namespaced layouters come back through here too
+    type Root = Self;
+
+    fn assign_region<A, AR, N, NR>(&mut self, name: N, assignment: A) -> Result<AR, Error>
+    where
+        A: FnMut(Region<'_, F>) -> Result<AR, Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        let start = Instant::now();
+        let result = self.inner.assign_region(&name, assignment);
+        let timing = RegionTiming { name: name().into(), elapsed: start.elapsed() };
+        PROFILE.with(|profile| profile.borrow_mut().push(timing));
+        result
+    }
+
+    fn assign_table<A, N, NR>(&mut self, name: N, assignment: A) -> Result<(), Error>
+    where
+        A: FnMut(Table<'_, F>) -> Result<(), Error>,
+        N: Fn() -> NR,
+        NR: Into<String>,
+    {
+        self.inner.assign_table(name, assignment)
+    }
+
+    fn constrain_instance(&mut self, cell: Cell, column: Column<Instance>, row: usize) -> Result<(), Error> {
+        self.inner.constrain_instance(cell, column, row)
+    }
+
+    fn get_root(&mut self) -> &mut Self::Root {
+        self
+    }
+
+    fn push_namespace<NR, N>(&mut self, name_fn: N)
+    where
+        NR: Into<String>,
+        N: FnOnce() -> NR,
+    {
+        self.inner.get_root().push_namespace(name_fn)
+    }
+
+    fn pop_namespace(&mut self, gadget_name: Option<String>) {
+        self.inner.get_root().pop_namespace(gadget_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::MockProver, pasta::Fp};
+
+    use super::*;
+    use crate::{dev::SelfVerifying, fibonacci::FibonacciCircuit};
+
+    #[test]
+    fn records_every_next_row() {
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 100);
+        take_region_profile();
+        MockProver::run(circuit.min_k(), &circuit, circuit.expected_public()).unwrap();
+
+        let profile = take_region_profile();
+        let rows: Vec<_> = profile.iter().filter(|timing| timing.name == "next row").collect();
+        // f(2) is the first row, f(3)..=f(100) one next row each
+        assert_eq!(rows.len(), 98);
+        assert!(rows.iter().all(|timing| timing.elapsed > Duration::ZERO));
+        assert!(take_region_profile().is_empty());
+    }
+}
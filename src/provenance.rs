@@ -0,0 +1,129 @@
+use std::{cell::RefCell, fmt};
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::MockProver,
+    plonk::*
+};
+
+use crate::fibonacci::{ACell, FiboChip, FiboConfig, FibonacciCircuit};
+
+// An ACell plus where it came from, for debugging copy constraints. The chain starts at the
+// cell the value was first assigned to and gets one entry per copy (or per derived cell),
+// so printing it shows the path a value took through the layout
+#[derive(Debug, Clone)]
+pub struct TrackedCell<F: FieldExt> {
+    pub cell: ACell<F>,
+    pub chain: Vec<String>,
+}
+
+impl<F: FieldExt> TrackedCell<F> {
+    pub fn new(cell: ACell<F>, origin: impl Into<String>) -> Self {
+        Self { cell, chain: vec![origin.into()] }
+    }
+
+    // copy_advice on the inner cell, the copy remembers the chain that led here
+    pub fn copy_advice(&self, annotation: &str, region: &mut Region<'_, F>, column: Column<Advice>, offset: usize) -> Result<Self, Error> {
+        let cell = self.cell.0.copy_advice(|| annotation, region, column, offset).map(ACell)?;
+        Ok(self.derived(cell, annotation))
+    }
+
+    // A cell computed from this one, or a copy made somewhere we can't reach (inside a chip)
+    pub fn derived(&self, cell: ACell<F>, label: impl Into<String>) -> Self {
+        let mut chain = self.chain.clone();
+        chain.push(label.into());
+        Self { cell, chain }
+    }
+
+    pub fn origin(&self) -> &str {
+        &self.chain[0]
+    }
+}
+
+impl<F: FieldExt> fmt::Display for TrackedCell<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.chain.join(" -> "))
+    }
+}
+
+// The trace of FibonacciCircuit (seeds not exposed) with every c cell tracked, built on the
+// cells compute_all returns. Each c is derived from the b of its row, which
+// FiboChip::assign_row copies from the previous c, so a chain follows the b input of every
+// add back to the first row
+pub fn compute_tracked<F: FieldExt>(circuit: &FibonacciCircuit<F>, chip: &FiboChip<F>, layouter: impl Layouter<F>) -> Result<Vec<TrackedCell<F>>, Error> {
+    let cells = circuit.clone().with_private_seeds().compute_all(chip, layouter)?;
+    let a = TrackedCell::new(cells[0].clone(), "a (first row)");
+    let b = TrackedCell::new(cells[1].clone(), "b (first row)");
+    let c = b.derived(cells[2].clone(), "c (first row)");
+
+    let mut tracked = vec![a, b, c];
+    for (row, c_cell) in cells[3..].iter().enumerate().map(|(i, cell)| (i + 1, cell)) {
+        // assign_row copies prev_c into b, then computes c from it
+        let prev_c = tracked.last().unwrap();
+        let b_copy = prev_c.derived(prev_c.cell.clone(), format!("b (row {})", row));
+        let c = b_copy.derived(c_cell.clone(), format!("c (row {})", row));
+        tracked.push(c);
+    }
+    Ok(tracked)
+}
+
+struct ProvenanceCircuit<F: FieldExt> {
+    inner: FibonacciCircuit<F>,
+    output: RefCell<Option<Vec<String>>>,
+}
+
+impl<F: FieldExt> Circuit<F> for ProvenanceCircuit<F> {
+    type Config = FiboConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { inner: self.inner.without_witnesses(), output: RefCell::new(None) }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        FibonacciCircuit::configure(meta)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config);
+        let cells = compute_tracked(&self.inner, &chip, layouter.namespace(|| "trace"))?;
+        let steps = self.inner.steps;
+        let output = if steps < 2 { &cells[steps] } else { cells.last().unwrap() };
+        *self.output.borrow_mut() = Some(output.chain.clone());
+        Ok(())
+    }
+}
+
+// The chain of the cell FibonacciCircuit exposes as its output. Only synthesizes, nothing
+// is checked, so it works on circuits that don't verify too
+pub fn output_provenance<F: FieldExt>(circuit: &FibonacciCircuit<F>) -> Vec<String> {
+    let circuit = ProvenanceCircuit { inner: circuit.clone(), output: RefCell::new(None) };
+    MockProver::run(circuit.inner.min_k(), &circuit, vec![vec![]]).unwrap();
+    circuit.output.into_inner().expect("synthesize ran")
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pasta::Fp;
+
+    use super::*;
+
+    #[test]
+    fn f9_output_traces_back_to_the_first_row() {
+        let chain = output_provenance(&FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9));
+        assert_eq!(chain.first().unwrap(), "b (first row)");
+        assert_eq!(chain[1], "c (first row)");
+        // f(3)..=f(9) are rows 1..=7, each a copy into b and a new c
+        assert_eq!(chain.len(), 2 + 2 * 7);
+        assert_eq!(chain.last().unwrap(), "c (row 7)");
+    }
+
+    #[test]
+    fn short_traces_stop_at_the_seeds() {
+        let chain = |steps| output_provenance(&FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), steps));
+        assert_eq!(chain(0), ["a (first row)"]);
+        assert_eq!(chain(1), ["b (first row)"]);
+    }
+}
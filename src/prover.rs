@@ -0,0 +1,679 @@
+use halo2_proofs::{
+    arithmetic::CurveAffine,
+    dev::MockProver,
+    pasta::{group::ff::PrimeField, EpAffine, EqAffine, Fp, Fq},
+    plonk::*,
+    poly::commitment::Params,
+    transcript::{Blake2bRead, Blake2bWrite, Challenge255},
+};
+use rand_core::RngCore;
+use rayon::prelude::*;
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{self, BufReader, BufWriter},
+    path::Path,
+    sync::Arc,
+};
+
+use crate::{dev::SelfVerifying, error::FiboError, fibonacci::FibonacciCircuit};
+
+// Thin wrappers around the real (non mock) halo2 prover and verifier. halo2 0.1 only has the
+// IPA commitment, so the helpers are generic over its curve: EqAffine (vesta, circuits over
+// Fp) everywhere in this crate, EpAffine (pallas) for circuits over Fq.
+// Public inputs are a single instance column, same as the circuits in this crate.
+
+// Generates the proving key, the verifying key is inside it (`pk.get_vk()`).
+// The circuit only provides the shape here, witnesses are not looked at
+pub fn keygen<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>>(
+    params: &Params<C>,
+    circuit: &ConcreteCircuit,
+) -> Result<ProvingKey<C>, Error> {
+    let vk = keygen_vk(params, circuit)?;
+    keygen_pk(params, vk, circuit)
+}
+
+// Bytes identifying a verifying key. halo2 0.1 has no vk serialization, but the pinned form
+// (domain, constraint system, fixed and permutation commitments) is exactly what the
+// transcript hashes, so its Debug output stands in for one. That makes the bytes
+// halo2's Debug formatting too: a halo2 release that prints the pinned key differently
+// changes them without any change to the key.
+// It only depends on the circuit shape, two circuits that differ only in their witnesses
+// have to give the same bytes, otherwise witness data leaked into keygen
+pub fn vk_bytes<C: CurveAffine>(vk: &VerifyingKey<C>) -> Vec<u8> {
+    format!("{:?}", vk.pinned()).into_bytes()
+}
+
+pub fn prove<C: CurveAffine, ConcreteCircuit: Circuit<C::Scalar>>(
+    params: &Params<C>,
+    pk: &ProvingKey<C>,
+    circuit: ConcreteCircuit,
+    public: &[C::Scalar],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, Error> {
+    let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
+    create_proof(params, pk, &[circuit], &[&[public]], rng, &mut transcript)?;
+    Ok(transcript.finalize())
+}
+
+pub fn verify<C: CurveAffine>(
+    params: &Params<C>,
+    vk: &VerifyingKey<C>,
+    public: &[C::Scalar],
+    proof: &[u8],
+) -> Result<(), Error> {
+    let strategy = SingleVerifier::new(params);
+    let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(proof);
+    verify_proof(params, vk, strategy, &[&[public]], &mut transcript)
+}
+
+// prove plus a check that the proof verifies. create_proof doesn't look at whether the
+// witness satisfies the circuit, a bad one just gives a proof that fails to verify. Either
+// way the error then comes with a MockProver pass over the same witness, naming the gates
+// and rows that fail instead of a bare Error
+pub fn prove_diagnosed<C, ConcreteCircuit>(
+    params: &Params<C>,
+    pk: &ProvingKey<C>,
+    circuit: ConcreteCircuit,
+    public: &[C::Scalar],
+    rng: impl RngCore,
+) -> Result<Vec<u8>, FiboError>
+where
+    C: CurveAffine,
+    ConcreteCircuit: Circuit<C::Scalar> + Clone,
+{
+    let error = match prove(params, pk, circuit.clone(), public, rng) {
+        Ok(proof) => match verify(params, pk.get_vk(), public, &proof) {
+            Ok(()) => return Ok(proof),
+            Err(e) => e,
+        },
+        Err(e) => e,
+    };
+
+    let k = pk.get_vk().get_domain().empty_lagrange().len().trailing_zeros();
+    let failures = match MockProver::run(k, &circuit, vec![public.to_vec()]) {
+        Ok(prover) => prover.verify().err().unwrap_or_default(),
+        // synthesis itself failed, nothing more to say than the error
+        Err(_) => vec![],
+    };
+    Err(FiboError::Diagnosed(error, failures))
+}
+
+// Remembers the last `capacity` verification results, keyed by a hash of the vk, the public
+// inputs and the proof, for services that see the same proof over and over. Evicts the
+// least recently used entry. A cached failure comes back as ConstraintSystemFailure,
+// whatever error the first verification gave
+pub struct VerifierCache {
+    capacity: usize,
+    // most recently used last
+    entries: VecDeque<([u8; 32], bool)>,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl VerifierCache {
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "a cache needs room for one entry");
+        Self { capacity, entries: VecDeque::new(), hits: 0, misses: 0 }
+    }
+
+    pub fn verify_cached<C: CurveAffine>(
+        &mut self,
+        params: &Params<C>,
+        vk: &VerifyingKey<C>,
+        public: &[C::Scalar],
+        proof: &[u8],
+    ) -> Result<(), Error> {
+        let key = cache_key(vk, public, proof);
+        let ok = match self.entries.iter().position(|(k, _)| *k == key) {
+            Some(i) => {
+                self.hits += 1;
+                let entry = self.entries.remove(i).unwrap();
+                self.entries.push_back(entry);
+                entry.1
+            }
+            None => {
+                self.misses += 1;
+                let ok = verify(params, vk, public, proof).is_ok();
+                if self.entries.len() == self.capacity {
+                    self.entries.pop_front();
+                }
+                self.entries.push_back((key, ok));
+                ok
+            }
+        };
+        if ok { Ok(()) } else { Err(Error::ConstraintSystemFailure) }
+    }
+}
+
+// Every variable length part goes in with its length first and the public inputs as their
+// canonical bytes, so no two (vk, public, proof) triples hash the same input
+fn cache_key<C: CurveAffine>(vk: &VerifyingKey<C>, public: &[C::Scalar], proof: &[u8]) -> [u8; 32] {
+    let vk = vk_bytes(vk);
+    let mut state = blake2b_simd::Params::new()
+        .hash_length(32)
+        .personal(b"halo2-circ-vcach")
+        .to_state();
+    state.update(&(vk.len() as u64).to_le_bytes());
+    state.update(&vk);
+    state.update(&(public.len() as u64).to_le_bytes());
+    for value in public {
+        state.update(value.to_repr().as_ref());
+    }
+    state.update(&(proof.len() as u64).to_le_bytes());
+    state.update(proof);
+    let mut key = [0u8; 32];
+    key.copy_from_slice(state.finalize().as_bytes());
+    key
+}
+
+// Proving keys of FibonacciCircuit by (k, steps), generated the first time a shape is asked
+// for and shared after that. The params are kept per k. `generated` counts the keygen runs
+pub struct PkCache {
+    params: HashMap<u32, Arc<Params<EqAffine>>>,
+    keys: HashMap<(u32, usize), Arc<ProvingKey<EqAffine>>>,
+    pub generated: usize,
+}
+
+impl PkCache {
+    pub fn new() -> Self {
+        Self { params: HashMap::new(), keys: HashMap::new(), generated: 0 }
+    }
+
+    pub fn params(&mut self, k: u32) -> Arc<Params<EqAffine>> {
+        self.params.entry(k).or_insert_with(|| Arc::new(Params::new(k))).clone()
+    }
+
+    pub fn get(&mut self, k: u32, steps: usize) -> Result<Arc<ProvingKey<EqAffine>>, Error> {
+        if let Some(pk) = self.keys.get(&(k, steps)) {
+            return Ok(pk.clone());
+        }
+        let params = self.params(k);
+        let pk = Arc::new(keygen(&params, &FibonacciCircuit::<Fp>::new(None, None, steps))?);
+        self.generated += 1;
+        self.keys.insert((k, steps), pk.clone());
+        Ok(pk)
+    }
+}
+
+impl Default for PkCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Proves f(steps) for public seeds at the smallest k, with the key from the cache
+pub fn prove_fibonacci(cache: &mut PkCache, steps: usize, seeds: (Fp, Fp)) -> Result<Vec<u8>, Error> {
+    let circuit = FibonacciCircuit::new(Some(seeds.0), Some(seeds.1), steps);
+    let public = circuit.expected_public().remove(0);
+    let k = circuit.min_k();
+    let pk = cache.get(k, steps)?;
+    prove(&cache.params(k), &pk, circuit, &public, ProofRng::os()?)
+}
+
+// Proves knowledge of seeds (a, b) with f(steps) = out, without revealing them: the seeds
+// are only witnessed and out is the only public input. halo2 blinds every advice column,
+// so the proof carries nothing about a and b beyond what out already says.
+// The keys come from the shape alone, verify_preimage rebuilds the same ones
+pub fn prove_preimage(steps: usize, out: Fp, secret_a: Fp, secret_b: Fp) -> Result<Vec<u8>, Error> {
+    let circuit = FibonacciCircuit::new(Some(secret_a), Some(secret_b), steps).with_private_seeds();
+    let params = Params::<EqAffine>::new(circuit.min_k());
+    let pk = keygen(&params, &circuit)?;
+    prove(&params, &pk, circuit, &[out], ProofRng::os()?)
+}
+
+pub fn verify_preimage(steps: usize, out: Fp, proof: &[u8]) -> Result<(), Error> {
+    let circuit = FibonacciCircuit::<Fp>::new(None, None, steps).with_private_seeds();
+    let params = Params::<EqAffine>::new(circuit.min_k());
+    let vk = keygen_vk(&params, &circuit)?;
+    verify(&params, &vk, &[out], proof)
+}
+
+// Everything a verifier needs for one proof of f(steps). halo2 0.1 can't serialize a verifying
+// key, so vk holds its vk_bytes and verify regenerates the key from steps, checking it
+// against those bytes before trusting it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofBundle {
+    pub steps: usize,
+    pub k: u32,
+    pub vk: Vec<u8>,
+    // seeds and output, as the circuit exposes them
+    pub public: Vec<Fp>,
+    pub proof: Vec<u8>,
+}
+
+impl ProofBundle {
+    pub fn verify(&self) -> Result<(), Error> {
+        let circuit = FibonacciCircuit::<Fp>::new(None, None, self.steps);
+        let params = Params::<EqAffine>::new(self.k);
+        let vk = keygen_vk(&params, &circuit)?;
+        if vk_bytes(&vk) != self.vk {
+            return Err(Error::ConstraintSystemFailure);
+        }
+        verify(&params, &vk, &self.public, &self.proof)
+    }
+}
+
+pub fn prove_bundle(steps: usize, seeds: (Fp, Fp)) -> Result<ProofBundle, Error> {
+    let circuit = FibonacciCircuit::new(Some(seeds.0), Some(seeds.1), steps);
+    let public = circuit.expected_public().remove(0);
+    let k = circuit.min_k();
+    let params = Params::<EqAffine>::new(k);
+    let pk = keygen(&params, &circuit)?;
+    let proof = prove(&params, &pk, circuit, &public, ProofRng::os()?)?;
+    Ok(ProofBundle { steps, k, vk: vk_bytes(pk.get_vk()), public, proof })
+}
+
+// Public values of an Fp proof as Fq elements, for a circuit over the other curve of the
+// cycle. Each value keeps its canonical integer: p < q, so that integer is already reduced
+// mod q and the map is one to one. It is not a field map though, x + y in Fp wraps at p
+// and the bridged values would have to carry that themselves
+pub fn bridge_outputs(proof_public: &[Fp]) -> Vec<Fq> {
+    proof_public.iter()
+        .map(|x| Option::from(Fq::from_repr(x.to_repr())).expect("p < q, every Fp value is a valid Fq value"))
+        .collect()
+}
+
+// Proves f(steps) over Fp, then a second sequence over Fq seeded with the last two public
+// values of the first (b and f(steps)) bridged across, and verifies both proofs. Only the
+// values cross over, nothing checks the first proof inside the second circuit
+pub fn prove_bridged(steps: usize, seeds: (Fp, Fp), next_steps: usize) -> Result<(), Error> {
+    let first = prove_bundle(steps, seeds)?;
+    first.verify()?;
+
+    let bridged = bridge_outputs(&first.public);
+    let second = FibonacciCircuit::new(Some(bridged[1]), Some(bridged[2]), next_steps);
+    let public = second.expected_public().remove(0);
+    let params = Params::<EpAffine>::new(second.min_k());
+    let pk = keygen(&params, &second)?;
+    let proof = prove(&params, &pk, second, &public, ProofRng::os()?)?;
+    verify(&params, pk.get_vk(), &public, &proof)
+}
+
+// Verifies proofs of different shapes (step counts, padding) together. Proofs are grouped by
+// verifying key and each group is batch verified, so the expensive final check runs once per
+// key instead of once per proof. The params are regenerated from the key's domain, which
+// assumes they came from `Params::new` like everywhere else in this crate.
+// Ok only if every proof verifies, a batch can't say which proof was bad
+pub fn verify_heterogeneous(entries: &[(VerifyingKey<EqAffine>, Vec<Fp>, Vec<u8>)]) -> Result<(), Error> {
+    let mut groups: Vec<(Vec<u8>, Vec<_>)> = vec![];
+    for entry in entries {
+        let key = vk_bytes(&entry.0);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((key, vec![entry])),
+        }
+    }
+
+    for (_, group) in groups {
+        let vk = &group[0].0;
+        let k = vk.get_domain().empty_lagrange().len().trailing_zeros();
+        let params = Params::<EqAffine>::new(k);
+
+        let mut strategy = BatchVerifier::new(&params, ProofRng::os()?);
+        for (_, public, proof) in group {
+            let mut transcript = Blake2bRead::<_, _, Challenge255<_>>::init(&proof[..]);
+            strategy = verify_proof(&params, vk, strategy, &[&[public]], &mut transcript)?;
+        }
+        if !strategy.finalize() {
+            return Err(Error::ConstraintSystemFailure);
+        }
+    }
+    Ok(())
+}
+
+// Proves independent instances of the same shape (steps and padding) on the rayon pool.
+// The keys are generated once and the proving key is shared between the threads
+pub fn prove_many_parallel(circuits: Vec<FibonacciCircuit<Fp>>, publics: Vec<Vec<Fp>>) -> Result<Vec<Vec<u8>>, Error> {
+    assert_eq!(circuits.len(), publics.len(), "one public input vector per circuit");
+    let first = match circuits.first() {
+        Some(first) => first,
+        None => return Ok(vec![]),
+    };
+    assert!(circuits.iter().all(|c| c.steps == first.steps && c.pad_k == first.pad_k),
+        "all circuits need the same shape to share a proving key");
+
+    let params = Arc::new(Params::<EqAffine>::new(first.min_k()));
+    let pk = Arc::new(keygen(&params, first)?);
+
+    circuits
+        .into_par_iter()
+        .zip(publics.into_par_iter())
+        .map(|(circuit, public)| prove(&params, &pk, circuit, &public, ProofRng::os()?))
+        .collect()
+}
+
+// Params take a while to generate for large k (Params::new is deterministic, but does a
+// multiexp per point), so generate them once and load them back on later runs. All the
+// helpers above take params by reference, loaded ones work the same as fresh ones.
+// Plain buffered reads: Params::read parses every point into memory anyway, so mapping
+// the file wouldn't buy much, and there is no mmap crate in the dependencies
+pub fn save_params<C: CurveAffine>(params: &Params<C>, path: impl AsRef<Path>) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    params.write(&mut writer)
+}
+
+pub fn load_params<C: CurveAffine>(path: impl AsRef<Path>) -> io::Result<Params<C>> {
+    Params::read(&mut BufReader::new(File::open(path)?))
+}
+
+// Randomness for the prover's blinding factors.
+// Os reads from the operating system and is what real proofs should use, Seeded is a
+// deterministic stream (Blake2b over the seed and a block counter) so tests can get
+// byte-identical proofs. Os reads /dev/urandom, so it's only there on unix, elsewhere
+// `os` returns Unsupported and Seeded is the only source
+pub enum ProofRng {
+    #[cfg(unix)]
+    Os(std::fs::File),
+    Seeded { seed: [u8; 32], counter: u64, block: Vec<u8> },
+}
+
+impl ProofRng {
+    pub fn os() -> std::io::Result<Self> {
+        #[cfg(unix)]
+        return std::fs::File::open("/dev/urandom").map(ProofRng::Os);
+        #[cfg(not(unix))]
+        return Err(io::Error::new(io::ErrorKind::Unsupported, "no OS randomness source, see ProofRng"));
+    }
+
+    pub fn seeded(seed: u64) -> Self {
+        let mut bytes = [0u8; 32];
+        bytes[..8].copy_from_slice(&seed.to_le_bytes());
+        ProofRng::Seeded { seed: bytes, counter: 0, block: vec![] }
+    }
+}
+
+impl RngCore for ProofRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.try_fill_bytes(dest).expect("reading randomness failed")
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        match self {
+            #[cfg(unix)]
+            ProofRng::Os(file) => {
+                use std::io::Read;
+                // rand_core without std can only carry an error code
+                let code = std::num::NonZeroU32::new(rand_core::Error::CUSTOM_START).unwrap();
+                file.read_exact(dest).map_err(|_| rand_core::Error::from(code))
+            }
+            ProofRng::Seeded { seed, counter, block } => {
+                for byte in dest {
+                    if block.is_empty() {
+                        let mut state = blake2b_simd::Params::new()
+                            .personal(b"halo2-circ-rng__")
+                            .to_state();
+                        state.update(seed).update(&counter.to_le_bytes());
+                        *block = state.finalize().as_bytes().to_vec();
+                        *counter += 1;
+                    }
+                    *byte = block.pop().unwrap();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dev::BrokenAdd;
+
+    fn seeded_proof(seed: u64) -> Vec<u8> {
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+        let public = circuit.expected_public().remove(0);
+        let params = Params::<EqAffine>::new(circuit.min_k());
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit, &public, ProofRng::seeded(seed)).unwrap();
+        verify(&params, pk.get_vk(), &public, &proof).unwrap();
+        proof
+    }
+
+    #[test]
+    fn seeded_proofs_are_reproducible() {
+        assert_eq!(seeded_proof(7), seeded_proof(7));
+        assert_ne!(seeded_proof(7), seeded_proof(8));
+    }
+
+    #[test]
+    fn vk_ignores_the_seeds() {
+        let params = Params::<EqAffine>::new(4);
+        let vk = |a: u64, b: u64| {
+            let circuit = FibonacciCircuit::new(Some(Fp::from(a)), Some(Fp::from(b)), 9);
+            vk_bytes(&keygen_vk(&params, &circuit).unwrap())
+        };
+        assert_eq!(vk(1, 1), vk(2, 3));
+    }
+
+    // vk, public inputs and a proof of f(steps) from seeds a, a + 1
+    fn entry(steps: usize, a: u64) -> (VerifyingKey<EqAffine>, Vec<Fp>, Vec<u8>) {
+        let circuit = FibonacciCircuit::new(Some(Fp::from(a)), Some(Fp::from(a + 1)), steps);
+        let public = circuit.expected_public().remove(0);
+        let params = Params::<EqAffine>::new(circuit.min_k());
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit, &public, ProofRng::seeded(a)).unwrap();
+        (pk.get_vk().clone(), public, proof)
+    }
+
+    #[test]
+    fn verifies_mixed_step_counts() {
+        let mut entries = vec![entry(9, 1), entry(15, 1), entry(9, 2), entry(15, 2)];
+        assert_ne!(vk_bytes(&entries[0].0), vk_bytes(&entries[1].0));
+        verify_heterogeneous(&entries).unwrap();
+
+        // the second f(15) proof against the first one's output
+        entries[3].1 = entries[1].1.clone();
+        assert!(verify_heterogeneous(&entries).is_err());
+    }
+
+    #[test]
+    fn proves_a_preimage_of_f9() {
+        let out = Fp::from(55);
+        let proof = prove_preimage(9, out, Fp::one(), Fp::one()).unwrap();
+        verify_preimage(9, out, &proof).unwrap();
+        assert!(verify_preimage(9, Fp::from(56), &proof).is_err());
+    }
+
+    #[test]
+    fn wrong_secret_gives_no_valid_proof() {
+        // seeds 1, 2 run to 89, not 55
+        let proof = prove_preimage(9, Fp::from(55), Fp::one(), Fp::from(2));
+        assert!(proof.map_or(true, |proof| verify_preimage(9, Fp::from(55), &proof).is_err()));
+    }
+
+    #[test]
+    fn proves_on_both_pasta_curves() {
+        use halo2_proofs::pasta::{EpAffine, Fq};
+
+        seeded_proof(0);
+
+        let circuit = FibonacciCircuit::new(Some(Fq::one()), Some(Fq::one()), 9);
+        let public = circuit.expected_public().remove(0);
+        let params = Params::<EpAffine>::new(circuit.min_k());
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit, &public, ProofRng::seeded(0)).unwrap();
+        verify(&params, pk.get_vk(), &public, &proof).unwrap();
+    }
+
+    #[test]
+    fn loaded_params_prove_and_verify() {
+        let path = std::env::temp_dir().join(format!("halo2-circuits-params-{}.bin", std::process::id()));
+        save_params(&Params::<EqAffine>::new(8), &path).unwrap();
+        let params: Params<EqAffine> = load_params(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        // f(9) padded to fill k = 8
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9).pad_to_k(8);
+        let public = circuit.expected_public().remove(0);
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit, &public, ProofRng::seeded(0)).unwrap();
+        verify(&params, pk.get_vk(), &public, &proof).unwrap();
+        verify(&Params::new(8), pk.get_vk(), &public, &proof).unwrap();
+    }
+
+    #[test]
+    fn bundle_verifies_on_its_own() {
+        let bundle = prove_bundle(9, (Fp::one(), Fp::one())).unwrap();
+        assert_eq!(bundle.public, vec![Fp::one(), Fp::one(), Fp::from(55)]);
+        bundle.verify().unwrap();
+
+        let mut forged = bundle.clone();
+        *forged.public.last_mut().unwrap() = Fp::from(56);
+        assert!(forged.verify().is_err());
+
+        // the vk regenerated for 10 steps is not the one in the bundle
+        let mut forged = bundle.clone();
+        forged.steps = 10;
+        assert!(forged.verify().is_err());
+    }
+
+    #[test]
+    fn cache_hits_on_the_same_proof() {
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+        let public = circuit.expected_public().remove(0);
+        let params = Params::<EqAffine>::new(circuit.min_k());
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit.clone(), &public, ProofRng::seeded(0)).unwrap();
+        let other = prove(&params, &pk, circuit, &public, ProofRng::seeded(1)).unwrap();
+
+        let mut cache = VerifierCache::new(2);
+        cache.verify_cached(&params, pk.get_vk(), &public, &proof).unwrap();
+        cache.verify_cached(&params, pk.get_vk(), &public, &proof).unwrap();
+        assert_eq!((cache.hits, cache.misses), (1, 1));
+
+        cache.verify_cached(&params, pk.get_vk(), &public, &other).unwrap();
+        assert_eq!((cache.hits, cache.misses), (1, 2));
+
+        // same proof, other public inputs, and the failure is cached too
+        let mut wrong = public.clone();
+        wrong[2] = Fp::from(56);
+        assert!(cache.verify_cached(&params, pk.get_vk(), &wrong, &proof).is_err());
+        assert!(cache.verify_cached(&params, pk.get_vk(), &wrong, &proof).is_err());
+        assert_eq!((cache.hits, cache.misses), (2, 3));
+
+        // two entries, the first proof was the least recently used
+        cache.verify_cached(&params, pk.get_vk(), &public, &proof).unwrap();
+        assert_eq!((cache.hits, cache.misses), (2, 4));
+    }
+
+    #[test]
+    fn cache_keys_keep_the_public_inputs_apart_from_the_proof() {
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+        let public = circuit.expected_public().remove(0);
+        let params = Params::<EqAffine>::new(circuit.min_k());
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove(&params, &pk, circuit, &public, ProofRng::seeded(0)).unwrap();
+
+        // the last public input moved to the front of the proof, which concatenating the Debug
+        // text of the inputs with the proof used to hash the same
+        let mut shifted = format!("{:?}", public[2]).into_bytes();
+        shifted.extend_from_slice(&proof);
+        assert_ne!(cache_key(pk.get_vk(), &public, &proof), cache_key(pk.get_vk(), &public[..2], &shifted));
+
+        let mut cache = VerifierCache::new(2);
+        cache.verify_cached(&params, pk.get_vk(), &public, &proof).unwrap();
+        assert!(cache.verify_cached(&params, pk.get_vk(), &public[..2], &shifted).is_err());
+        assert_eq!((cache.hits, cache.misses), (0, 2));
+    }
+
+    #[test]
+    fn bridged_values_keep_their_integers() {
+        let public = [Fp::one(), Fp::from(55), -Fp::one()];
+        let bridged = bridge_outputs(&public);
+        assert_eq!(bridged[..2], [Fq::one(), Fq::from(55)]);
+        // p - 1 stays p - 1, it is not -1 in Fq
+        assert_eq!(bridged[2].to_repr(), public[2].to_repr());
+        assert_ne!(bridged[2], -Fq::one());
+    }
+
+    #[test]
+    fn bridges_f9_into_an_fq_circuit() {
+        prove_bridged(9, (Fp::one(), Fp::one()), 5).unwrap();
+
+        // the same chain by hand: b = 1 and f(9) = 55 seed the Fq sequence 1, 55, 56, 111, 167, 278
+        let first = prove_bundle(9, (Fp::one(), Fp::one())).unwrap();
+        let bridged = bridge_outputs(&first.public);
+        let second = FibonacciCircuit::new(Some(bridged[1]), Some(bridged[2]), 5);
+        let public = second.expected_public().remove(0);
+        assert_eq!(public, vec![Fq::one(), Fq::from(55), Fq::from(278)]);
+        let params = Params::<EpAffine>::new(second.min_k());
+        let pk = keygen(&params, &second).unwrap();
+        let proof = prove(&params, &pk, second, &public, ProofRng::seeded(0)).unwrap();
+        verify(&params, pk.get_vk(), &public, &proof).unwrap();
+    }
+
+    #[test]
+    fn pk_cache_generates_once_per_shape() {
+        let mut cache = PkCache::new();
+        for seeds in [(1, 1), (2, 3)] {
+            let seeds = (Fp::from(seeds.0), Fp::from(seeds.1));
+            let proof = prove_fibonacci(&mut cache, 9, seeds).unwrap();
+            let circuit = FibonacciCircuit::new(Some(seeds.0), Some(seeds.1), 9);
+            let k = circuit.min_k();
+            verify(&cache.params(k), cache.get(k, 9).unwrap().get_vk(), &circuit.expected_public()[0], &proof).unwrap();
+        }
+        assert_eq!(cache.generated, 1);
+
+        prove_fibonacci(&mut cache, 15, (Fp::one(), Fp::one())).unwrap();
+        assert_eq!(cache.generated, 2);
+    }
+
+    #[test]
+    fn diagnosed_proof_names_the_failing_gate() {
+        let params = Params::<EqAffine>::new(4);
+        let pk = keygen(&params, &BrokenAdd).unwrap();
+        let error = prove_diagnosed(&params, &pk, BrokenAdd, &BrokenAdd.expected_public()[0], ProofRng::seeded(0)).unwrap_err();
+        let FiboError::Diagnosed(_, failures) = &error else { panic!("not diagnosed: {}", error) };
+        assert_eq!(failures.len(), 1);
+        let report = error.to_string();
+        assert!(report.contains("('add')"), "{}", report);
+        // the row after the first one
+        assert!(report.contains("Region 1 ('broken row') at offset 0"), "{}", report);
+    }
+
+    #[test]
+    fn diagnosed_proof_of_a_good_witness_verifies() {
+        let circuit = FibonacciCircuit::new(Some(Fp::one()), Some(Fp::one()), 9);
+        let public = circuit.expected_public().remove(0);
+        let params = Params::<EqAffine>::new(4);
+        let pk = keygen(&params, &circuit).unwrap();
+        let proof = prove_diagnosed(&params, &pk, circuit, &public, ProofRng::seeded(0)).unwrap();
+        verify(&params, pk.get_vk(), &public, &proof).unwrap();
+    }
+
+    #[test]
+    fn parallel_proofs_verify() {
+        let circuits: Vec<_> = (1..=4u64)
+            .map(|seed| FibonacciCircuit::new(Some(Fp::from(seed)), Some(Fp::from(seed + 1)), 9))
+            .collect();
+        let publics: Vec<_> = circuits.iter().map(|c| c.expected_public().remove(0)).collect();
+        let proofs = prove_many_parallel(circuits.clone(), publics.clone()).unwrap();
+        assert_eq!(proofs.len(), 4);
+
+        let params = Params::<EqAffine>::new(circuits[0].min_k());
+        let vk = keygen_vk(&params, &circuits[0]).unwrap();
+        for (public, proof) in publics.iter().zip(&proofs) {
+            verify(&params, &vk, public, proof).unwrap();
+        }
+        // each proof is bound to its own public inputs
+        assert!(verify(&params, &vk, &publics[0], &proofs[1]).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn os_rng_reports_a_failed_read() {
+        // /dev/null is always at its end
+        let mut rng = ProofRng::Os(File::open("/dev/null").unwrap());
+        assert!(rng.try_fill_bytes(&mut [0u8; 8]).is_err());
+        assert!(ProofRng::os().unwrap().try_fill_bytes(&mut [0u8; 8]).is_ok());
+    }
+}
@@ -0,0 +1,105 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    dev::find_min_k,
+    fibonacci::ACell,
+    linear::{LinearChip, LinearConfig},
+    mul::{MulChip, MulConfig},
+};
+
+// Public a, b, c with a^2 + b^2 = c^2. The squares are mul gates, the sum a linear combination
+// with target 0. Nothing is range checked: the values are public, and for a, b, c below 2^64
+// the squares and their sum stay far below the modulus, so the field equation is the integer one
+#[derive(Debug, Clone)]
+pub struct PythagoreanConfig {
+    pub mul: MulConfig,
+    pub linear: LinearConfig,
+    pub instance: Column<Instance>,
+}
+
+// a, b and c come from instance rows 0, 1 and 2, the circuit itself holds nothing
+#[derive(Default, Clone)]
+pub struct PythagoreanCircuit<F> {
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> PythagoreanCircuit<F> {
+    pub fn new() -> Self {
+        Self { _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for PythagoreanCircuit<F> {
+    type Config = PythagoreanConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self::new()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        PythagoreanConfig {
+            mul: MulChip::configure(meta),
+            linear: LinearChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        // the three public values, in the mul chip's columns
+        let sides = layouter.assign_region(|| "sides", |mut region| {
+            let mut sides = vec![];
+            for (row, column) in config.mul.advice.into_iter().enumerate() {
+                sides.push(region.assign_advice_from_instance(|| "side", config.instance, row, column, 0).map(ACell)?);
+            }
+            Ok(sides)
+        })?;
+
+        let mul = MulChip::construct(config.mul);
+        let squares = sides.iter()
+            .map(|side| mul.square(layouter.namespace(|| "square"), side))
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        LinearChip::construct(config.linear).linear_combination_eq(layouter.namespace(|| "a^2 + b^2 = c^2"),
+            &squares, &[F::one(), F::one(), -F::one()], F::zero())
+    }
+}
+
+// Mock proves that (a, b, c) is a Pythagorean triple, all three are public
+pub fn prove_pythagorean(a: u64, b: u64, c: u64) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = PythagoreanCircuit::<Fp>::new();
+    let public = vec![vec![Fp::from(a), Fp::from(b), Fp::from(c)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_triples() {
+        prove_pythagorean(3, 4, 5).unwrap();
+        prove_pythagorean(5, 12, 13).unwrap();
+        let public = vec![Fp::from(5), Fp::from(12), Fp::from(13)];
+        assert!(crate::dev::full_roundtrip(&PythagoreanCircuit::new(), 4, public));
+    }
+
+    #[test]
+    fn rejects_3_4_6() {
+        // 9 + 16 != 36, only the final sum is off
+        let failures = prove_pythagorean(3, 4, 6).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+}
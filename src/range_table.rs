@@ -0,0 +1,31 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*
+};
+
+// Lookup table holding 0..2^BITS, so a chip can range check a value with a single lookup
+// Needs k > BITS, the table takes 2^BITS rows
+#[derive(Debug, Clone)]
+pub struct RangeTableConfig<F: FieldExt, const BITS: usize> {
+    pub value: TableColumn,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt, const BITS: usize> RangeTableConfig<F, BITS> {
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let value = meta.lookup_table_column();
+        Self { value, _marker: PhantomData }
+    }
+
+    pub fn load(&self, layouter: &mut impl Layouter<F>) -> Result<(), Error> {
+        layouter.assign_table(|| "range table", |mut table| {
+            for i in 0..(1 << BITS) {
+                table.assign_cell(|| "value", self.value, i, || Ok(F::from(i as u64)))?;
+            }
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,224 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::{dev::find_min_k, fibonacci::ACell};
+
+// One step of an iterated computation on a state of WIDTH field elements. step is the
+// native version, constraints the same step as expressions over this row's state and the
+// next one's, each of which has to be zero. Anything the step depends on besides the state
+// is part of the type (see Lcg), the constraints are fixed at configure time
+pub trait Transition<F: FieldExt> {
+    const WIDTH: usize;
+
+    fn step(state: &[F]) -> Vec<F>;
+
+    fn constraints(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<Expression<F>>;
+}
+
+// (f(n), f(n+1)) -> (f(n+1), f(n+2))
+#[derive(Debug, Clone, Default)]
+pub struct FibonacciTransition;
+
+impl<F: FieldExt> Transition<F> for FibonacciTransition {
+    const WIDTH: usize = 2;
+
+    fn step(state: &[F]) -> Vec<F> {
+        vec![state[1], state[0] + state[1]]
+    }
+
+    fn constraints(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<Expression<F>> {
+        vec![
+            next[0].clone() - cur[1].clone(),
+            next[1].clone() - cur[0].clone() - cur[1].clone(),
+        ]
+    }
+}
+
+// Linear congruential step x -> A * x + C, the modulus being the field's
+#[derive(Debug, Clone, Default)]
+pub struct Lcg<const A: u64, const C: u64>;
+
+impl<F: FieldExt, const A: u64, const C: u64> Transition<F> for Lcg<A, C> {
+    const WIDTH: usize = 1;
+
+    fn step(state: &[F]) -> Vec<F> {
+        vec![F::from(A) * state[0] + F::from(C)]
+    }
+
+    fn constraints(cur: &[Expression<F>], next: &[Expression<F>]) -> Vec<Expression<F>> {
+        let (a, c) = (Expression::Constant(F::from(A)), Expression::Constant(F::from(C)));
+        vec![next[0].clone() - (a * cur[0].clone() + c)]
+    }
+}
+
+// The state after `steps` steps, outside the circuit
+pub fn sequence_native<F: FieldExt, T: Transition<F>>(init: &[F], steps: usize) -> Vec<F> {
+    assert_eq!(init.len(), T::WIDTH, "the state is {} elements", T::WIDTH);
+    (0..steps).fold(init.to_vec(), |state, _| T::step(&state))
+}
+
+// One column per state element and one row per state, the step gate between every row
+// and the next
+#[derive(Debug, Clone)]
+pub struct SequenceConfig {
+    pub state: Vec<Column<Advice>>,
+    pub step: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct SequenceChip<F: FieldExt, T> {
+    config: SequenceConfig,
+    _marker: PhantomData<(F, T)>,
+}
+
+impl<F: FieldExt, T: Transition<F>> SequenceChip<F, T> {
+    pub fn construct(config: SequenceConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> SequenceConfig {
+        let state: Vec<_> = (0..T::WIDTH).map(|_| meta.advice_column()).collect();
+        let step = meta.selector();
+
+        for &column in &state {
+            meta.enable_equality(column);
+        }
+        meta.enable_equality(instance);
+
+        meta.create_gate("sequence step", |meta| {
+            // state[0] | state[1] | .. | selector
+            //   s(n)   |   s(n)   | .. |   step
+            //  s(n+1)  |  s(n+1)  | .. |
+            let s = meta.query_selector(step);
+            let cur: Vec<_> = state.iter().map(|&column| meta.query_advice(column, Rotation::cur())).collect();
+            let next: Vec<_> = state.iter().map(|&column| meta.query_advice(column, Rotation::next())).collect();
+            T::constraints(&cur, &next).into_iter().map(|constraint| s.clone() * constraint).collect::<Vec<_>>()
+        });
+
+        SequenceConfig { state, step, instance }
+    }
+
+    // Copies the initial state in from instance rows 0..WIDTH and runs `steps` steps,
+    // returns the final state
+    pub fn assign(&self, mut layouter: impl Layouter<F>, steps: usize) -> Result<Vec<ACell<F>>, Error> {
+        layouter.assign_region(|| "sequence", |mut region| {
+            let mut cells = self.config.state.iter().enumerate()
+                .map(|(i, &column)| region.assign_advice_from_instance(|| "init", self.config.instance, i, column, 0).map(ACell))
+                .collect::<Result<Vec<_>, Error>>()?;
+
+            for row in 1..=steps {
+                self.config.step.enable(&mut region, row - 1)?;
+                // None until there is a witness, e.g. during keygen
+                let state: Option<Vec<F>> = cells.iter().map(|cell| cell.0.value().copied()).collect();
+                let next = state.map(|state| T::step(&state));
+                cells = self.config.state.iter().enumerate()
+                    .map(|(i, &column)| {
+                        let value = next.as_ref().map(|next| next[i]);
+                        region.assign_advice(|| "state", column, row, || value.ok_or(Error::Synthesis)).map(ACell)
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+            }
+            Ok(cells)
+        })
+    }
+
+    // The final state at instance rows WIDTH..2 * WIDTH, after the initial one
+    pub fn expose_final(&self, mut layouter: impl Layouter<F>, state: &[ACell<F>]) -> Result<(), Error> {
+        for (i, cell) in state.iter().enumerate() {
+            layouter.constrain_instance(cell.0.cell(), self.config.instance, T::WIDTH + i)?;
+        }
+        Ok(())
+    }
+}
+
+// `steps` steps of T, the initial and the final state public. steps is part of the shape,
+// the states come from the instance column
+#[derive(Default, Clone)]
+pub struct SequenceCircuit<F, T> {
+    pub steps: usize,
+    _marker: PhantomData<(F, T)>,
+}
+
+impl<F: FieldExt, T: Transition<F>> SequenceCircuit<F, T> {
+    pub fn new(steps: usize) -> Self {
+        Self { steps, _marker: PhantomData }
+    }
+
+    // The initial state followed by its state after `steps` steps
+    pub fn public(&self, init: &[F]) -> Vec<Vec<F>> {
+        let mut public = init.to_vec();
+        public.extend(sequence_native::<F, T>(init, self.steps));
+        vec![public]
+    }
+}
+
+impl<F: FieldExt, T: Transition<F> + Clone> Circuit<F> for SequenceCircuit<F, T> {
+    type Config = SequenceConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        SequenceChip::<F, T>::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = SequenceChip::<F, T>::construct(config);
+        let last = chip.assign(layouter.namespace(|| "steps"), self.steps)?;
+        chip.expose_final(layouter.namespace(|| "final state"), &last)
+    }
+}
+
+// Mock proves that `last` is the state `steps` steps of T after init
+pub fn prove_sequence<T: Transition<Fp> + Clone>(init: &[Fp], steps: usize, last: &[Fp]) -> Result<(), Vec<VerifyFailure>> {
+    assert_eq!(init.len(), T::WIDTH, "the state is {} elements", T::WIDTH);
+    let circuit = SequenceCircuit::<Fp, T>::new(steps);
+    let mut public = init.to_vec();
+    public.extend_from_slice(last);
+    let public = vec![public];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Lcg53 = Lcg<5, 3>;
+
+    #[test]
+    fn fibonacci_as_a_transition() {
+        let init = [Fp::one(), Fp::one()];
+        // (f(8), f(9))
+        let last = [Fp::from(34), Fp::from(55)];
+        assert_eq!(sequence_native::<Fp, FibonacciTransition>(&init, 8), last);
+        assert_eq!(prove_sequence::<FibonacciTransition>(&init, 8, &last), Ok(()));
+    }
+
+    #[test]
+    fn lcg_as_a_transition() {
+        // 1, 8, 43, 218
+        let circuit = SequenceCircuit::<Fp, Lcg53>::new(3);
+        assert_eq!(circuit.public(&[Fp::one()]), vec![vec![Fp::one(), Fp::from(218)]]);
+        assert_eq!(prove_sequence::<Lcg53>(&[Fp::one()], 3, &[Fp::from(218)]), Ok(()));
+        assert!(crate::dev::full_roundtrip(&circuit, 4, circuit.public(&[Fp::one()]).remove(0)));
+    }
+
+    #[test]
+    fn rejects_the_wrong_final_state() {
+        let failures = prove_sequence::<Lcg53>(&[Fp::one()], 3, &[Fp::from(219)]).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+        assert!(prove_sequence::<FibonacciTransition>(&[Fp::one(), Fp::one()], 8, &[Fp::from(55), Fp::from(34)]).is_err());
+    }
+}
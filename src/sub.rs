@@ -0,0 +1,199 @@
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    fibonacci::{cell_value, ACell},
+};
+
+// Fixed width subtraction: a - b + borrow * 2^width = result, with result range checked to
+// width bits and borrow a bit. The inputs are expected to be width bits already, range check
+// them with the bits chip first if they aren't.
+#[derive(Debug, Clone)]
+pub struct SubConfig {
+    pub advice: [Column<Advice>; 4], // a, b, result, borrow
+    // 2^width, per row since the width is picked when assigning
+    pub shift: Column<Fixed>,
+    pub select: Selector,
+    pub bits: BitsConfig,
+}
+
+pub struct SubChip<F: FieldExt> {
+    config: SubConfig,
+    _marker: std::marker::PhantomData<F>,
+}
+
+impl<F: FieldExt> SubChip<F> {
+    pub fn construct(config: SubConfig) -> Self {
+        Self { config, _marker: std::marker::PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SubConfig {
+        let advice = [(); 4].map(|_| meta.advice_column());
+        let shift = meta.fixed_column();
+        let selector = meta.selector();
+        let bits = BitsChip::configure(meta);
+
+        for column in advice {
+            meta.enable_equality(column);
+        }
+
+        meta.create_gate("sub", |meta| {
+            // a | b | result | borrow | shift  | selector
+            // a   b     r        w      2^width     s
+            let s = meta.query_selector(selector);
+            let [a, b, r, w] = advice.map(|column| meta.query_advice(column, Rotation::cur()));
+            let shift = meta.query_fixed(shift, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                s.clone() * (a - b + w.clone() * shift - r),
+                s * w.clone() * (one - w),
+            ]
+        });
+
+        SubConfig { advice, shift, select: selector, bits }
+    }
+
+    // Returns (result, borrow)
+    pub fn sub_with_borrow(&self, mut layouter: impl Layouter<F>, a: &ACell<F>, b: &ACell<F>, width: usize) -> Result<(ACell<F>, ACell<F>), Error> {
+        assert!((1..128).contains(&width), "width has to be in 1..128");
+
+        let (result, borrow) = layouter.assign_region(|| "sub", |mut region| {
+            self.config.select.enable(&mut region, 0)?;
+
+            a.0.copy_advice(|| "a", &mut region, self.config.advice[0], 0)?;
+            b.0.copy_advice(|| "b", &mut region, self.config.advice[1], 0)?;
+            region.assign_fixed(|| "2^width", self.config.shift, 0,
+                || Ok(F::from_u128(1 << width)))?;
+
+            let (r_val, w_val) = match (cell_value(a), cell_value(b)) {
+                (Some(a), Some(b)) => {
+                    let (a, b) = (a.get_lower_128(), b.get_lower_128());
+                    if a >= b {
+                        (Some(F::from_u128(a - b)), Some(F::zero()))
+                    } else {
+                        (Some(F::from_u128((1 << width) + a - b)), Some(F::one()))
+                    }
+                }
+                _ => (None, None),
+            };
+
+            let result = region.assign_advice(|| "result", self.config.advice[2], 0,
+                || r_val.ok_or(Error::Synthesis)).map(ACell)?;
+            let borrow = region.assign_advice(|| "borrow", self.config.advice[3], 0,
+                || w_val.ok_or(Error::Synthesis)).map(ACell)?;
+            Ok((result, borrow))
+        })?;
+
+        BitsChip::construct(self.config.bits.clone())
+            .decompose(layouter.namespace(|| "result range"), &result, width)?;
+
+        Ok((result, borrow))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::layouter_ext::LayouterExt;
+
+    // a - b at 8 bits, the result at instance row 0 and the borrow at row 1
+    #[derive(Clone)]
+    struct SubCircuit {
+        a: u64,
+        b: u64,
+    }
+
+    impl Circuit<Fp> for SubCircuit {
+        type Config = (SubConfig, Column<Instance>);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            meta.enable_equality(instance);
+            (SubChip::configure(meta), instance)
+        }
+
+        fn synthesize(&self, (config, instance): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let a = layouter.assign_value("a", config.advice[0], Some(Fp::from(self.a)))?;
+            let b = layouter.assign_value("b", config.advice[1], Some(Fp::from(self.b)))?;
+            let (result, borrow) = SubChip::construct(config).sub_with_borrow(layouter.namespace(|| "a - b"), &a, &b, 8)?;
+            layouter.constrain_instance(result.0.cell(), instance, 0)?;
+            layouter.constrain_instance(borrow.0.cell(), instance, 1)
+        }
+    }
+
+    // 3 - 5 with the result and borrow written by hand, the gate and the range check as in
+    // sub_with_borrow
+    #[derive(Clone)]
+    struct ForgedSub {
+        result: Fp,
+        borrow: Fp,
+    }
+
+    impl Circuit<Fp> for ForgedSub {
+        type Config = SubConfig;
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            SubChip::configure(meta)
+        }
+
+        fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let result = layouter.assign_region(|| "sub", |mut region| {
+                config.select.enable(&mut region, 0)?;
+                region.assign_advice(|| "a", config.advice[0], 0, || Ok(Fp::from(3)))?;
+                region.assign_advice(|| "b", config.advice[1], 0, || Ok(Fp::from(5)))?;
+                region.assign_fixed(|| "2^width", config.shift, 0, || Ok(Fp::from(256)))?;
+                region.assign_advice(|| "borrow", config.advice[3], 0, || Ok(self.borrow))?;
+                region.assign_advice(|| "result", config.advice[2], 0, || Ok(self.result)).map(ACell)
+            })?;
+            BitsChip::construct(config.bits).decompose(layouter.namespace(|| "result range"), &result, 8)?;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn rejects_a_result_out_of_range() {
+        crate::assert_circuit!(ForgedSub { result: Fp::from(254), borrow: Fp::one() }, 5, vec![]);
+        // 3 - 5 = -2 without the borrow holds in the gate, the range check catches it
+        crate::assert_circuit_fails!(ForgedSub { result: -Fp::from(2), borrow: Fp::zero() }, 5, vec![]);
+        // borrow 2 and 510 balance the sum, but the borrow isn't a bit (and 510 not 8 bits)
+        crate::assert_circuit_fails!(ForgedSub { result: Fp::from(510), borrow: Fp::from(2) }, 5, vec![]);
+    }
+
+    fn public(result: u64, borrow: u64) -> Vec<Vec<Fp>> {
+        vec![vec![Fp::from(result), Fp::from(borrow)]]
+    }
+
+    #[test]
+    fn no_borrow() {
+        crate::assert_circuit!(SubCircuit { a: 5, b: 3 }, 5, public(2, 0));
+    }
+
+    #[test]
+    fn borrow_wraps_the_result() {
+        crate::assert_circuit!(SubCircuit { a: 3, b: 5 }, 5, public(254, 1));
+    }
+
+    #[test]
+    fn rejects_wrong_claims() {
+        crate::assert_circuit_fails!(SubCircuit { a: 5, b: 3 }, 5, public(2, 1), VerifyFailure::Permutation { .. });
+        crate::assert_circuit_fails!(SubCircuit { a: 3, b: 5 }, 5, public(254, 0), VerifyFailure::Permutation { .. });
+    }
+}
@@ -0,0 +1,205 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::{
+    cursor::RowCursor,
+    dev::find_min_k,
+    fibonacci::{cell_value, ACell, FiboChip, FiboConfig, FibonacciCircuit},
+    layouter_ext::LayouterExt,
+};
+
+// Some of the given terms add up to the target. One row per term with a copy of the term,
+// a boolean mask bit and the running sum of the masked terms, which has to end equal to
+// the target. Unlike ZeckendorfChip the terms are cells (e.g. straight from the trace)
+// and any subset goes, neighbours included
+#[derive(Debug, Clone)]
+pub struct SubsetSumConfig {
+    pub term: Column<Advice>,
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub first: Selector,
+    pub next: Selector,
+}
+
+pub struct SubsetSumChip<F: FieldExt> {
+    config: SubsetSumConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SubsetSumChip<F> {
+    pub fn construct(config: SubsetSumConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SubsetSumConfig {
+        let term = meta.advice_column();
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let first = meta.selector();
+        let next = meta.selector();
+
+        meta.enable_equality(term);
+        meta.enable_equality(acc);
+
+        meta.create_gate("subset sum first", |meta| {
+            // term | bit | acc           | selector
+            //  t0  | b0  | b0 * t0       | first
+            //  t1  | b1  | acc + b1 * t1 | next
+            let first = meta.query_selector(first);
+            let t = meta.query_advice(term, Rotation::cur());
+            let b = meta.query_advice(bit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                first.clone() * b.clone() * (one - b.clone()),
+                first * (acc - b * t),
+            ]
+        });
+
+        meta.create_gate("subset sum next", |meta| {
+            let next = meta.query_selector(next);
+            let t = meta.query_advice(term, Rotation::cur());
+            let b = meta.query_advice(bit, Rotation::cur());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let one = Expression::Constant(F::one());
+            vec![
+                next.clone() * b.clone() * (one - b.clone()),
+                next * (acc_cur - (acc_prev + b * t)),
+            ]
+        });
+
+        SubsetSumConfig { term, bit, acc, first, next }
+    }
+
+    // mask picks the terms, one bit per term. None until there is a witness, e.g. during keygen
+    pub fn subset_sum(&self, mut layouter: impl Layouter<F>, terms: &[ACell<F>], mask: Option<&[bool]>, target: &ACell<F>) -> Result<(), Error> {
+        if let Some(mask) = mask {
+            assert_eq!(terms.len(), mask.len(), "one mask bit per term");
+        }
+        assert!(!terms.is_empty(), "no terms");
+
+        layouter.assign_region(|| "subset sum", |mut region| {
+            let mut cursor = RowCursor::new(&mut region);
+            let mut acc_val = Some(F::zero());
+            let mut acc_cell = None;
+            for (row, term) in terms.iter().enumerate() {
+                let bit = mask.map(|mask| mask[row]);
+                if row > 0 {
+                    cursor.next_row();
+                    cursor.enable(self.config.next)?;
+                } else {
+                    cursor.enable(self.config.first)?;
+                }
+                cursor.copy("term", self.config.term, term)?;
+                cursor.assign("bit", self.config.bit, bit.map(|bit| F::from(bit as u64)))?;
+
+                acc_val = match bit {
+                    Some(true) => acc_val.and_then(|acc| cell_value(term).map(|t| acc + t)),
+                    Some(false) => acc_val,
+                    None => None,
+                };
+                acc_cell = Some(cursor.assign("acc", self.config.acc, acc_val)?);
+            }
+            cursor.region().constrain_equal(acc_cell.unwrap().0.cell(), target.0.cell())
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubsetSumCircuitConfig {
+    pub fibo: FiboConfig,
+    pub subset: SubsetSumConfig,
+}
+
+// Public seeds and target, the masked terms f(0)..f(steps) of the trace add up to the target.
+// The mask is the witness, one bit per term
+#[derive(Default, Clone)]
+pub struct SubsetSumCircuit<F> {
+    pub fibo: FibonacciCircuit<F>,
+    pub mask: Option<Vec<bool>>,
+}
+
+impl<F: FieldExt> Circuit<F> for SubsetSumCircuit<F> {
+    type Config = SubsetSumCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { fibo: self.fibo.without_witnesses(), mask: None }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SubsetSumCircuitConfig {
+            fibo: FibonacciCircuit::configure(meta),
+            subset: SubsetSumChip::configure(meta),
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = FiboChip::construct(config.fibo.clone());
+        let terms = self.fibo.compute_all(&chip, layouter.namespace(|| "trace"))?;
+
+        let target = layouter.assign_from_instance("target", config.fibo.instance, 2, config.subset.acc)?;
+        SubsetSumChip::construct(config.subset)
+            .subset_sum(layouter.namespace(|| "subset sum"), &terms, self.mask.as_deref(), &target)
+    }
+}
+
+// Mock proves that the masked terms f(0)..f(steps) for the given seeds add up to target,
+// one mask bit per term
+pub fn prove_subset_sum(seeds: (Fp, Fp), steps: usize, mask: &[bool], target: u64) -> Result<(), Vec<VerifyFailure>> {
+    assert_eq!(mask.len(), steps + 1, "one mask bit per term f(0)..f(steps)");
+    let circuit = SubsetSumCircuit {
+        fibo: FibonacciCircuit::new(Some(seeds.0), Some(seeds.1), steps),
+        mask: Some(mask.to_vec()),
+    };
+    let public = vec![vec![seeds.0, seeds.1, Fp::from(target)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Seeds 1, 2 give the terms 1, 2, 3, 5, 8, 13 as f(0)..f(5)
+    const SEEDS: (u64, u64) = (1, 2);
+
+    fn seeds() -> (Fp, Fp) {
+        (Fp::from(SEEDS.0), Fp::from(SEEDS.1))
+    }
+
+    #[test]
+    fn picks_2_5_13_for_20() {
+        let mask = [false, true, false, true, false, true];
+        assert_eq!(prove_subset_sum(seeds(), 5, &mask, 20), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_mask_that_misses_the_target() {
+        // 1 + 2 + 5 + 13 = 21
+        let mask = [true, true, false, true, false, true];
+        let failures = prove_subset_sum(seeds(), 5, &mask, 20).unwrap_err();
+        assert!(failures.iter().any(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+    }
+
+    #[test]
+    fn keygen_without_the_mask() {
+        use halo2_proofs::{pasta::EqAffine, poly::commitment::Params};
+
+        let circuit = SubsetSumCircuit {
+            fibo: FibonacciCircuit::new(Some(seeds().0), Some(seeds().1), 5),
+            mask: Some(vec![false, true, false, true, false, true]),
+        };
+        let params: Params<EqAffine> = Params::new(6);
+        assert!(keygen_vk(&params, &circuit.without_witnesses()).is_ok());
+    }
+}
@@ -0,0 +1,161 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    plonk::*, poly::Rotation
+};
+
+use crate::fibonacci::{cell_value, ACell};
+
+// f(0) + f(1) + ... + f(n) = f(n+2) - 1 (for seeds with f(1) = 1), so a running sum of the
+// terms can be checked against a single later term
+#[derive(Debug, Clone)]
+pub struct SumIdentityConfig {
+    pub advice: [Column<Advice>; 2],
+    pub first: Selector,
+    pub next: Selector,
+    pub check: Selector,
+}
+
+pub struct SumIdentityChip<F: FieldExt> {
+    config: SumIdentityConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SumIdentityChip<F> {
+    pub fn construct(config: SumIdentityConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SumIdentityConfig {
+        let col_value = meta.advice_column();
+        let col_acc = meta.advice_column();
+        let first = meta.selector();
+        let next = meta.selector();
+        let check = meta.selector();
+
+        meta.enable_equality(col_value);
+        meta.enable_equality(col_acc);
+
+        meta.create_gate("sum first", |meta| {
+            // value | acc       | selector
+            //   v0  | v0        | first
+            //   v1  | acc + v1  | next
+            let first = meta.query_selector(first);
+            let v = meta.query_advice(col_value, Rotation::cur());
+            let acc = meta.query_advice(col_acc, Rotation::cur());
+            vec![first * (acc - v)]
+        });
+
+        meta.create_gate("sum next", |meta| {
+            let next = meta.query_selector(next);
+            let v = meta.query_advice(col_value, Rotation::cur());
+            let acc_cur = meta.query_advice(col_acc, Rotation::cur());
+            let acc_prev = meta.query_advice(col_acc, Rotation::prev());
+            vec![next * (acc_cur - (acc_prev + v))]
+        });
+
+        meta.create_gate("sum identity", |meta| {
+            // sum | f(n+2) | selector
+            let check = meta.query_selector(check);
+            let sum = meta.query_advice(col_value, Rotation::cur());
+            let fn2 = meta.query_advice(col_acc, Rotation::cur());
+            vec![check * (sum - fn2 + Expression::Constant(F::one()))]
+        });
+
+        SumIdentityConfig { advice: [col_value, col_acc], first, next, check }
+    }
+
+    // Running sum of the cells, returns the total
+    pub fn accumulate(&self, mut layouter: impl Layouter<F>, cells: &[ACell<F>]) -> Result<ACell<F>, Error> {
+        assert!(!cells.is_empty(), "nothing to sum");
+
+        layouter.assign_region(|| "sum", |mut region| {
+            let mut acc_val = Some(F::zero());
+            let mut acc_cell = None;
+            for (row, cell) in cells.iter().enumerate() {
+                if row == 0 {
+                    self.config.first.enable(&mut region, row)?;
+                } else {
+                    self.config.next.enable(&mut region, row)?;
+                }
+                cell.0.copy_advice(|| "value", &mut region, self.config.advice[0], row)?;
+
+                acc_val = acc_val.and_then(|acc| cell_value(cell).map(|v| acc + v));
+                acc_cell = Some(region.assign_advice(|| "acc", self.config.advice[1], row,
+                    || acc_val.ok_or(Error::Synthesis)).map(ACell)?);
+            }
+            Ok(acc_cell.unwrap())
+        })
+    }
+
+    pub fn sum_identity_check(&self, mut layouter: impl Layouter<F>, sum_cell: &ACell<F>, fn2_cell: &ACell<F>) -> Result<(), Error> {
+        layouter.assign_region(|| "sum identity", |mut region| {
+            self.config.check.enable(&mut region, 0)?;
+            sum_cell.0.copy_advice(|| "sum", &mut region, self.config.advice[0], 0)?;
+            fn2_cell.0.copy_advice(|| "f(n+2)", &mut region, self.config.advice[1], 0)?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::{dev::VerifyFailure, pasta::Fp};
+
+    use super::*;
+    use crate::{
+        dev::full_roundtrip,
+        fibonacci::{FiboChip, FiboConfig, FibonacciCircuit},
+        layouter_ext::LayouterExt,
+    };
+
+    // f(0) + .. + f(7) against f(9) for the public seeds 0, 1. A nonzero `off_by` swaps the
+    // accumulated sum for a free witness that much larger
+    #[derive(Clone)]
+    struct SumCircuit {
+        off_by: u64,
+    }
+
+    impl Circuit<Fp> for SumCircuit {
+        type Config = (FiboConfig, SumIdentityConfig);
+
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            self.clone()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fp>) -> Self::Config {
+            let instance = meta.instance_column();
+            (FiboChip::configure(meta, instance), SumIdentityChip::configure(meta))
+        }
+
+        fn synthesize(&self, (fibo, config): Self::Config, mut layouter: impl Layouter<Fp>) -> Result<(), Error> {
+            let trace = FibonacciCircuit::new(Some(Fp::zero()), Some(Fp::one()), 9);
+            let cells = trace.compute_all(&FiboChip::construct(fibo), layouter.namespace(|| "trace"))?;
+
+            let chip = SumIdentityChip::construct(config.clone());
+            let mut sum = chip.accumulate(layouter.namespace(|| "f(0..=7)"), &cells[..=7])?;
+            if self.off_by > 0 {
+                let forged = cell_value(&sum).map(|s| s + Fp::from(self.off_by));
+                sum = layouter.assign_value("forged sum", config.advice[0], forged)?;
+            }
+            chip.sum_identity_check(layouter.namespace(|| "identity"), &sum, &cells[9])
+        }
+    }
+
+    #[test]
+    fn identity_holds_for_7() {
+        // 0 + 1 + 1 + 2 + 3 + 5 + 8 + 13 = 33 = f(9) - 1
+        assert!(full_roundtrip(&SumCircuit { off_by: 0 }, 5, vec![Fp::zero(), Fp::one()]));
+    }
+
+    #[test]
+    fn rejects_a_sum_off_by_one() {
+        let circuit = SumCircuit { off_by: 1 };
+        crate::assert_circuit_fails!(circuit, 5, vec![vec![Fp::zero(), Fp::one()]], VerifyFailure::ConstraintNotSatisfied { .. });
+        assert!(!full_roundtrip(&circuit, 5, vec![Fp::zero(), Fp::one()]));
+    }
+}
@@ -0,0 +1,143 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    arith::{ArithChip, ArithConfig},
+    dev::{find_min_k, SelfVerifying},
+    fibonacci::{ACell, FiboChip, FiboConfig, FibonacciCircuit},
+    layouter_ext::LayouterExt,
+};
+
+// f(n)^2 + f(n+1)^2 = f(2n+1), for f(0) = 0 and f(1) = 1. Both squares and their sum go
+// through the arith gates, the sum is then copy constrained to the later term
+#[derive(Debug, Clone)]
+pub struct SumOfSquaresConfig {
+    pub arith: ArithConfig,
+}
+
+pub struct SumOfSquaresChip<F: FieldExt> {
+    config: SumOfSquaresConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SumOfSquaresChip<F> {
+    pub fn construct(config: SumOfSquaresConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>) -> SumOfSquaresConfig {
+        SumOfSquaresConfig { arith: ArithChip::configure(meta, true) }
+    }
+
+    pub fn sum_of_squares_check(&self, mut layouter: impl Layouter<F>, f_n: &ACell<F>, f_n1: &ACell<F>,
+        f_2n1: &ACell<F>) -> Result<(), Error> {
+        let arith = ArithChip::construct(self.config.arith.clone());
+        let x = arith.mul(layouter.namespace(|| "f(n)^2"), f_n, f_n)?;
+        let y = arith.mul(layouter.namespace(|| "f(n+1)^2"), f_n1, f_n1)?;
+        let sum = arith.add(layouter.namespace(|| "f(n)^2 + f(n+1)^2"), &x, &y)?;
+        layouter.assign_region(|| "sum = f(2n+1)", |mut region| {
+            let sum = sum.0.copy_advice(|| "sum", &mut region, self.config.arith.advice[0], 0)?;
+            region.constrain_equal(sum.cell(), f_2n1.0.cell())
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SumOfSquaresCircuitConfig {
+    pub fibo: FiboConfig,
+    pub squares: SumOfSquaresConfig,
+}
+
+// The sequence from public seeds 0, 1 up to f(2n+1), exposed as usual. With
+// sum_of_squares_check it also runs the identity at n over the trace cells. A forged
+// f(2n+1) is witnessed on its own in place of the trace cell, to see the check reject it
+#[derive(Default, Clone)]
+pub struct SumOfSquaresCircuit<F> {
+    pub n: usize,
+    pub sum_of_squares_check: bool,
+    pub forged: Option<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> SumOfSquaresCircuit<F> {
+    pub fn new(n: usize) -> Self {
+        Self { n, sum_of_squares_check: true, forged: None, _marker: PhantomData }
+    }
+
+    pub fn forged(n: usize, f_2n1: u64) -> Self {
+        Self { forged: Some(f_2n1), ..Self::new(n) }
+    }
+
+    pub fn fibo(&self) -> FibonacciCircuit<F> {
+        FibonacciCircuit::new(Some(F::zero()), Some(F::one()), 2 * self.n + 1)
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for SumOfSquaresCircuit<F> {
+    type Config = SumOfSquaresCircuitConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        self.clone()
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        SumOfSquaresCircuitConfig {
+            fibo: FibonacciCircuit::configure(meta),
+            squares: SumOfSquaresChip::configure(meta),
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let fibo = self.fibo();
+        let chip = FiboChip::construct(config.fibo);
+        let cells = fibo.compute_all(&chip, layouter.namespace(|| "trace"))?;
+        chip.expose_public(layouter.namespace(|| "output"), &cells[fibo.steps], fibo.output_row())?;
+
+        if self.sum_of_squares_check {
+            let f_2n1 = match self.forged {
+                Some(forged) => layouter.assign_value("forged f(2n+1)", config.squares.arith.advice[0], Some(F::from(forged)))?,
+                None => cells[2 * self.n + 1].clone(),
+            };
+            SumOfSquaresChip::construct(config.squares).sum_of_squares_check(layouter.namespace(|| "sum of squares"),
+                &cells[self.n], &cells[self.n + 1], &f_2n1)?;
+        }
+        Ok(())
+    }
+}
+
+// Mock proves the identity at n, the public inputs are those of the plain trace
+pub fn prove_sum_of_squares(circuit: SumOfSquaresCircuit<Fp>) -> Result<(), Vec<VerifyFailure>> {
+    let public = circuit.fibo().expected_public();
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn holds_at_4() {
+        // 3^2 + 5^2 = 34
+        assert_eq!(prove_sum_of_squares(SumOfSquaresCircuit::new(4)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_forged_term() {
+        let failures = prove_sum_of_squares(SumOfSquaresCircuit::forged(4, 35)).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::Permutation { .. })));
+
+        // without the check nothing looks at the forged term
+        let unchecked = SumOfSquaresCircuit { sum_of_squares_check: false, ..SumOfSquaresCircuit::forged(4, 35) };
+        assert_eq!(prove_sum_of_squares(unchecked), Ok(()));
+    }
+}
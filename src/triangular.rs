@@ -0,0 +1,140 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*
+};
+
+use crate::{
+    bits::{BitsChip, BitsConfig},
+    dev::find_min_k,
+    fibonacci::ACell,
+    linear::{LinearChip, LinearConfig},
+    mul::{MulChip, MulConfig},
+};
+
+// n is range checked to this width, enough for every t below 2^64
+pub const TRIANGULAR_BITS: usize = 33;
+
+// Public t is the n-th triangular number: 2t = n * (n + 1) for a witnessed n. The product is
+// the mul gate, n + 1 and the factor 2 are linear combinations with constant targets.
+// In the field every t with 8t + 1 a square has some n, so n is range checked as well,
+// which keeps n * (n + 1) from wrapping around the modulus
+#[derive(Debug, Clone)]
+pub struct TriangularConfig {
+    pub mul: MulConfig,
+    pub linear: LinearConfig,
+    pub bits: BitsConfig,
+    pub instance: Column<Instance>,
+}
+
+// The layout is the same for every t, t is only the witness (and the public input)
+#[derive(Default, Clone)]
+pub struct TriangularCircuit<F> {
+    pub t: Option<u64>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> TriangularCircuit<F> {
+    pub fn new(t: u64) -> Self {
+        Self { t: Some(t), _marker: PhantomData }
+    }
+
+    // the n with n * (n + 1) / 2 <= t < (n + 1) * (n + 2) / 2, which only satisfies the
+    // circuit if t is triangular. None without a witness
+    pub fn n(&self) -> Option<u64> {
+        let t = self.t? as u128;
+        let root = ((8 * t + 1) as f64).sqrt() as u128;
+        let mut n = root.saturating_sub(1) / 2;
+        while (n + 1) * (n + 2) / 2 <= t {
+            n += 1;
+        }
+        while n * (n + 1) / 2 > t {
+            n -= 1;
+        }
+        Some(n as u64)
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for TriangularCircuit<F> {
+    type Config = TriangularConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { t: None, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        meta.enable_equality(instance);
+        TriangularConfig {
+            mul: MulChip::configure(meta),
+            linear: LinearChip::configure(meta),
+            bits: BitsChip::configure(meta),
+            instance,
+        }
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let n = self.n();
+        // free cells for the witnesses, in the mul chip's columns
+        let [col_n, col_m, col_t] = config.mul.advice;
+        let (n, m, t) = layouter.assign_region(|| "witness", |mut region| {
+            let n_cell = region.assign_advice(|| "n", col_n, 0,
+                || n.map(F::from).ok_or(Error::Synthesis)).map(ACell)?;
+            let m_cell = region.assign_advice(|| "n + 1", col_m, 0,
+                || n.map(|n| F::from(n + 1)).ok_or(Error::Synthesis)).map(ACell)?;
+            let t_cell = region.assign_advice_from_instance(|| "t", config.instance, 0, col_t, 0).map(ACell)?;
+            Ok((n_cell, m_cell, t_cell))
+        })?;
+
+        BitsChip::construct(config.bits).decompose(layouter.namespace(|| "n range"), &n, TRIANGULAR_BITS)?;
+        let product = MulChip::construct(config.mul).mul(layouter.namespace(|| "n * (n + 1)"), &n, &m)?;
+
+        let linear = LinearChip::construct(config.linear);
+        linear.linear_combination_eq(layouter.namespace(|| "m = n + 1"), &[m, n],
+            &[F::one(), -F::one()], F::one())?;
+        linear.linear_combination_eq(layouter.namespace(|| "2t = n * (n + 1)"), &[product, t],
+            &[F::one(), -F::from(2)], F::zero())
+    }
+}
+
+// Mock proves that t is a triangular number, fails for any other t
+pub fn prove_triangular(t: u64) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = TriangularCircuit::<Fp>::new(t);
+    let public = vec![vec![Fp::from(t)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_triangular_numbers() {
+        assert_eq!(TriangularCircuit::<Fp>::new(10).n(), Some(4));
+        prove_triangular(10).unwrap();
+        assert_eq!(TriangularCircuit::<Fp>::new(15).n(), Some(5));
+        prove_triangular(15).unwrap();
+    }
+
+    #[test]
+    fn rejects_11() {
+        // n = 4 is the closest, 4 * 5 = 20 != 22
+        assert!(prove_triangular(11).is_err());
+        let circuit = TriangularCircuit::<Fp>::new(11);
+        assert!(!crate::dev::full_roundtrip(&circuit, 6, vec![Fp::from(11)]));
+    }
+
+    #[test]
+    fn keygen_without_t() {
+        let circuit = TriangularCircuit::<Fp>::new(15);
+        assert_eq!(circuit.without_witnesses().n(), None);
+        assert!(crate::dev::full_roundtrip(&circuit, 6, vec![Fp::from(15)]));
+    }
+}
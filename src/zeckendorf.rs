@@ -0,0 +1,353 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::{
+    arithmetic::FieldExt,
+    circuit::*,
+    dev::{MockProver, VerifyFailure},
+    pasta::Fp,
+    plonk::*, poly::Rotation
+};
+
+use crate::dev::find_min_k;
+
+// Every n >= 1 is a unique sum of non-consecutive Fibonacci numbers (Zeckendorf).
+// One row per term 1, 2, 3, 5, ... up to n, the bit says whether the term is used.
+// The running sum of the used terms has to end at the public n, and no two neighbouring
+// bits can both be set
+#[derive(Debug, Clone)]
+pub struct ZeckendorfConfig {
+    pub bit: Column<Advice>,
+    pub acc: Column<Advice>,
+    pub term: Column<Fixed>,
+    pub first: Selector,
+    pub next: Selector,
+    // the terminating 1 of a Fibonacci code, see `validate_fib_code`
+    pub end: Selector,
+    pub instance: Column<Instance>,
+}
+
+pub struct ZeckendorfChip<F: FieldExt> {
+    config: ZeckendorfConfig,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ZeckendorfChip<F> {
+    pub fn construct(config: ZeckendorfConfig) -> Self {
+        Self { config, _marker: PhantomData }
+    }
+
+    pub fn configure(meta: &mut ConstraintSystem<F>, instance: Column<Instance>) -> ZeckendorfConfig {
+        let bit = meta.advice_column();
+        let acc = meta.advice_column();
+        let term = meta.fixed_column();
+        let first = meta.selector();
+        let next = meta.selector();
+        let end = meta.selector();
+
+        meta.enable_equality(acc);
+        meta.enable_equality(instance);
+
+        meta.create_gate("zeckendorf first", |meta| {
+            // bit | acc            | term | selector
+            //  b0 | b0 * t0        |  t0  | first
+            //  b1 | acc + b1 * t1  |  t1  | next
+            let first = meta.query_selector(first);
+            let bit = meta.query_advice(bit, Rotation::cur());
+            let acc = meta.query_advice(acc, Rotation::cur());
+            let t = meta.query_fixed(term, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                first.clone() * bit.clone() * (one - bit.clone()),
+                first * (acc - bit * t),
+            ]
+        });
+
+        meta.create_gate("zeckendorf next", |meta| {
+            let next = meta.query_selector(next);
+            let bit_cur = meta.query_advice(bit, Rotation::cur());
+            let bit_prev = meta.query_advice(bit, Rotation::prev());
+            let acc_cur = meta.query_advice(acc, Rotation::cur());
+            let acc_prev = meta.query_advice(acc, Rotation::prev());
+            let t = meta.query_fixed(term, Rotation::cur());
+            let one = Expression::Constant(F::one());
+            vec![
+                next.clone() * bit_cur.clone() * (one - bit_cur.clone()),
+                // non-consecutive
+                next.clone() * bit_cur.clone() * bit_prev,
+                next * (acc_cur - (acc_prev + bit_cur * t)),
+            ]
+        });
+
+        meta.create_gate("fib code end", |meta| {
+            // the row after the digits, under end only so it may follow a 1
+            //  1  |                | end
+            let end = meta.query_selector(end);
+            let bit_cur = meta.query_advice(bit, Rotation::cur());
+            let bit_prev = meta.query_advice(bit, Rotation::prev());
+            let one = Expression::Constant(F::one());
+            vec![
+                end.clone() * (one.clone() - bit_cur),
+                end * (one - bit_prev),
+            ]
+        });
+
+        ZeckendorfConfig { bit, acc, term, first, next, end, instance }
+    }
+
+    // The terms are fixed, only the bits are witnessed (None without a witness, e.g. during
+    // keygen). Returns the final sum
+    pub fn assign_bits(&self, mut layouter: impl Layouter<F>, terms: &[u64], bits: Option<&[bool]>) -> Result<AssignedCell<F, F>, Error> {
+        if let Some(bits) = bits {
+            assert_eq!(terms.len(), bits.len(), "one bit per term");
+        }
+        assert!(!terms.is_empty(), "no terms");
+
+        layouter.assign_region(|| "zeckendorf", |mut region| self.assign_rows(&mut region, terms, bits))
+    }
+
+    // One first/next row per term from row 0, returns the running sum of the last one
+    fn assign_rows(&self, region: &mut Region<'_, F>, terms: &[u64], bits: Option<&[bool]>) -> Result<AssignedCell<F, F>, Error> {
+        let mut acc_val = Some(F::zero());
+        let mut acc_cell = None;
+        for (row, &term) in terms.iter().enumerate() {
+            let bit = bits.map(|bits| bits[row]);
+            if row == 0 {
+                self.config.first.enable(region, row)?;
+            } else {
+                self.config.next.enable(region, row)?;
+            }
+            region.assign_fixed(|| "term", self.config.term, row, || Ok(F::from(term)))?;
+            region.assign_advice(|| "bit", self.config.bit, row,
+                || bit.map(|bit| F::from(bit as u64)).ok_or(Error::Synthesis))?;
+
+            acc_val = acc_val.zip(bit).map(|(acc, bit)| if bit { acc + F::from(term) } else { acc });
+            acc_cell = Some(region.assign_advice(|| "acc", self.config.acc, row, || acc_val.ok_or(Error::Synthesis))?);
+        }
+        Ok(acc_cell.unwrap())
+    }
+
+    // A Fibonacci code is the Zeckendorf bits of n, lowest term first, followed by a 1: it ends
+    // in 11 and has no other neighbouring 1s. The digits go through the same rows as
+    // assign_bits, the terminator through the end gate, which also pins the last digit to 1.
+    // The code is len bits long, bits is None without a witness like in assign_bits.
+    // Returns the decoded n
+    pub fn validate_fib_code(&self, mut layouter: impl Layouter<F>, len: usize, bits: Option<&[bool]>) -> Result<AssignedCell<F, F>, Error> {
+        assert!(len >= 2, "a Fibonacci code is at least the two bits 11");
+        if let Some(bits) = bits {
+            assert_eq!(bits.len(), len, "the code is {} bits", len);
+        }
+        let split = bits.map(|bits| bits.split_at(len - 1));
+        let terms = fib_code_terms(len - 1);
+
+        layouter.assign_region(|| "fib code", |mut region| {
+            let n = self.assign_rows(&mut region, &terms, split.map(|(digits, _)| digits))?;
+
+            let row = len - 1;
+            let terminator = split.map(|(_, terminator)| F::from(terminator[0] as u64));
+            self.config.end.enable(&mut region, row)?;
+            region.assign_advice(|| "terminator", self.config.bit, row, || terminator.ok_or(Error::Synthesis))?;
+            Ok(n)
+        })
+    }
+
+    pub fn expose_public(&self, mut layouter: impl Layouter<F>, cell: &AssignedCell<F, F>, row: usize) -> Result<(), Error> {
+        layouter.constrain_instance(cell.cell(), self.config.instance, row)
+    }
+}
+
+// Fibonacci numbers 1, 2, 3, 5, ... up to n
+pub fn zeckendorf_terms(n: u64) -> Vec<u64> {
+    let mut terms = vec![];
+    // u128 so the term after the last one that fits can't overflow
+    let (mut a, mut b) = (1u128, 2u128);
+    while a <= n as u128 {
+        terms.push(a as u64);
+        (a, b) = (b, a + b);
+    }
+    terms
+}
+
+// The first `len` Zeckendorf terms 1, 2, 3, 5, .., panics past the ones that fit in a u64
+pub fn fib_code_terms(len: usize) -> Vec<u64> {
+    // u128 like zeckendorf_terms, the pair runs one term ahead
+    let terms: Vec<u64> = std::iter::successors(Some((1u128, 2u128)), |&(a, b)| Some((b, a + b)))
+        .map_while(|(a, _)| u64::try_from(a).ok())
+        .take(len)
+        .collect();
+    assert!(terms.len() == len, "only {} terms fit in a u64", terms.len());
+    terms
+}
+
+// Fibonacci code of n >= 1, its Zeckendorf bits and the terminating 1
+pub fn fib_code(n: u64) -> Vec<bool> {
+    let mut bits = zeckendorf_bits(n);
+    bits.push(true);
+    bits
+}
+
+// Greedy, take the largest term that still fits. Never picks two neighbours since
+// f(i) + f(i+1) = f(i+2) would have fit instead
+pub fn zeckendorf_bits(n: u64) -> Vec<bool> {
+    let terms = zeckendorf_terms(n);
+    let mut rest = n;
+    let mut bits = vec![false; terms.len()];
+    for (i, &term) in terms.iter().enumerate().rev() {
+        if term <= rest {
+            bits[i] = true;
+            rest -= term;
+        }
+    }
+    bits
+}
+
+// The terms depend on n, so n is part of the circuit shape as well as the public input.
+// The bits are the witness
+#[derive(Default, Clone)]
+pub struct ZeckendorfCircuit<F> {
+    pub n: u64,
+    pub bits: Option<Vec<bool>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> ZeckendorfCircuit<F> {
+    pub fn new(n: u64) -> Self {
+        Self::with_bits(n, zeckendorf_bits(n))
+    }
+
+    // Any representation, for checking the circuit rejects the wrong ones
+    pub fn with_bits(n: u64, bits: Vec<bool>) -> Self {
+        Self { n, bits: Some(bits), _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for ZeckendorfCircuit<F> {
+    type Config = ZeckendorfConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { n: self.n, bits: None, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        ZeckendorfChip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = ZeckendorfChip::construct(config);
+        let sum = chip.assign_bits(layouter.namespace(|| "bits"), &zeckendorf_terms(self.n), self.bits.as_deref())?;
+        chip.expose_public(layouter.namespace(|| "n"), &sum, 0)
+    }
+}
+
+// Mock proves that bits is a Zeckendorf representation of n
+pub fn prove_zeckendorf_with(n: u64, bits: Vec<bool>) -> Result<(), Vec<VerifyFailure>> {
+    assert!(n >= 1, "0 has no Zeckendorf representation");
+
+    let circuit = ZeckendorfCircuit::<Fp>::with_bits(n, bits);
+    let public = vec![vec![Fp::from(n)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+// Mock proves the greedy representation of n
+pub fn prove_zeckendorf(n: u64) -> Result<(), Vec<VerifyFailure>> {
+    prove_zeckendorf_with(n, zeckendorf_bits(n))
+}
+
+// A bit sequence that has to be a well formed Fibonacci code, its decoded value public.
+// The length is part of the shape, the bits are the witness
+#[derive(Default, Clone)]
+pub struct FibCodeCircuit<F> {
+    pub len: usize,
+    pub bits: Option<Vec<bool>>,
+    _marker: PhantomData<F>,
+}
+
+impl<F: FieldExt> FibCodeCircuit<F> {
+    pub fn new(bits: Vec<bool>) -> Self {
+        Self { len: bits.len(), bits: Some(bits), _marker: PhantomData }
+    }
+}
+
+impl<F: FieldExt> Circuit<F> for FibCodeCircuit<F> {
+    type Config = ZeckendorfConfig;
+
+    type FloorPlanner = SimpleFloorPlanner;
+
+    fn without_witnesses(&self) -> Self {
+        Self { len: self.len, bits: None, _marker: PhantomData }
+    }
+
+    fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
+        let instance = meta.instance_column();
+        ZeckendorfChip::configure(meta, instance)
+    }
+
+    fn synthesize(&self, config: Self::Config, mut layouter: impl Layouter<F>) -> Result<(), Error> {
+        let chip = ZeckendorfChip::construct(config);
+        let n = chip.validate_fib_code(layouter.namespace(|| "code"), self.len, self.bits.as_deref())?;
+        chip.expose_public(layouter.namespace(|| "n"), &n, 0)
+    }
+}
+
+// Mock proves that bits is a Fibonacci code of n
+pub fn prove_fib_code(bits: Vec<bool>, n: u64) -> Result<(), Vec<VerifyFailure>> {
+    let circuit = FibCodeCircuit::<Fp>::new(bits);
+    let public = vec![vec![Fp::from(n)]];
+    let k = find_min_k(|| (circuit.clone(), public.clone()));
+    MockProver::run(k, &circuit, public).unwrap().verify()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn greedy_representations() {
+        assert_eq!(zeckendorf_bits(10), [false, true, false, false, true]);
+        prove_zeckendorf(10).unwrap();
+        // 89 + 8 + 3
+        assert_eq!(zeckendorf_bits(100).iter().filter(|&&bit| bit).count(), 3);
+        prove_zeckendorf(100).unwrap();
+    }
+
+    #[test]
+    fn rejects_consecutive_terms() {
+        // 10 = 5 + 3 + 2, the right sum over the neighbouring terms 2 and 3
+        let failures = prove_zeckendorf_with(10, vec![false, true, true, true, false]).unwrap_err();
+        assert!(failures.iter().all(|failure| matches!(failure, VerifyFailure::ConstraintNotSatisfied { .. })));
+        assert!(failures.iter().any(|failure| failure.to_string().contains("zeckendorf next")));
+    }
+
+    #[test]
+    fn keygen_without_the_bits() {
+        let circuit = ZeckendorfCircuit::<Fp>::new(100);
+        assert!(circuit.without_witnesses().bits.is_none());
+        assert!(crate::dev::full_roundtrip(&circuit, 4, vec![Fp::from(100)]));
+    }
+
+    #[test]
+    fn accepts_the_code_of_10() {
+        // 2 + 8, then the terminator
+        let code = fib_code(10);
+        assert_eq!(code, [false, true, false, false, true, true]);
+        assert_eq!(prove_fib_code(code.clone(), 10), Ok(()));
+        assert!(crate::dev::full_roundtrip(&FibCodeCircuit::<Fp>::new(code), 4, vec![Fp::from(10)]));
+    }
+
+    #[test]
+    fn rejects_a_11_before_the_terminator() {
+        // 2 + 3 + 8 = 13 with 2 and 3 next to each other
+        let failures = prove_fib_code(vec![false, true, true, false, true, true], 13).unwrap_err();
+        assert!(failures.iter().any(|failure| failure.to_string().contains("zeckendorf next")));
+    }
+
+    #[test]
+    fn rejects_a_code_without_the_final_11() {
+        // 1 + 3, followed by a 1 that doesn't come after another 1
+        let failures = prove_fib_code(vec![true, false, true, false, true], 4).unwrap_err();
+        assert!(failures.iter().any(|failure| failure.to_string().contains("fib code end")));
+    }
+}